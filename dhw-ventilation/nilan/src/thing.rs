@@ -291,6 +291,45 @@ fn make_ventilation() -> Arc<RwLock<Box<dyn Thing + 'static>>> {
         ),
     )));
 
+    thing.add_property(Box::new(BaseProperty::new(
+        "supplied_air_fan_speed".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "Supplied air fan speed",
+                "type": "integer",
+                "description": "The fan speed for the supplied air",
+                "minimum": 0,
+                "unit": "rpm",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+    thing.add_property(Box::new(BaseProperty::new(
+        "extracted_air_fan_speed".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "Extracted air fan speed",
+                "type": "integer",
+                "description": "The fan speed for the extracted air",
+                "minimum": 0,
+                "unit": "rpm",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
     thing.add_available_action(
         "stop".to_owned(),
         json!({
@@ -598,6 +637,16 @@ pub fn run(address: SocketAddr, port: Option<u16>) {
                 "inside_co2_level",
                 ventilation_state.inside_co2_level
             );
+            update_property!(
+                ventilation,
+                "supplied_air_fan_speed",
+                ventilation_state.air_throughput.supplied_air_fan_speed
+            );
+            update_property!(
+                ventilation,
+                "extracted_air_fan_speed",
+                ventilation_state.air_throughput.extracted_air_fan_speed
+            );
         }
 
         thread::sleep(time::Duration::from_secs(60));
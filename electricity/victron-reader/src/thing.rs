@@ -1,4 +1,4 @@
-use crate::reader;
+use crate::{reader, state::BatteryState};
 use serde_json::{json, Value};
 use std::{
     sync::{Arc, RwLock, Weak},
@@ -92,6 +92,44 @@ fn make_battery() -> Arc<RwLock<Box<dyn Thing + 'static>>> {
         ),
     )));
 
+    thing.add_property(Box::new(BaseProperty::new(
+        "state".to_owned(),
+        json!("idle"),
+        None,
+        Some(
+            json!({
+                "title": "State",
+                "type": "string",
+                "enum": ["idle", "charging", "discharging"],
+                "description": "Whether the battery is idle, charging or discharging",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+    thing.add_property(Box::new(BaseProperty::new(
+        "health".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "State of Health",
+                "type": "number",
+                "description": "The battery state of health",
+                "minimum": 0,
+                "maximum": 100,
+                "unit": "percent",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
     Arc::new(RwLock::new(Box::new(thing)))
 }
 
@@ -326,6 +364,16 @@ pub fn run(mut context: sync::Context, port: Option<u16>) {
             update_property!(battery, "voltage", battery_state.voltage);
             update_property!(battery, "temperature", battery_state.temperature);
             update_property!(battery, "state_of_charge", battery_state.state_of_charge);
+            update_property!(
+                battery,
+                "state",
+                match battery_state.state {
+                    BatteryState::Idle => "idle",
+                    BatteryState::Charging => "charging",
+                    BatteryState::Discharging => "discharging",
+                }
+            );
+            update_property!(battery, "health", battery_state.health);
         }
 
         let pv_inverter_state = state.pv_inverter.unwrap_or_else(|| Default::default());
@@ -0,0 +1,2 @@
+-- The extension is kept: dropping it would drop all the hypertables.
+SELECT 1;
@@ -0,0 +1,2 @@
+-- Load the Timescale extension, required by all the hypertables.
+CREATE EXTENSION IF NOT EXISTS timescaledb;
@@ -0,0 +1,25 @@
+-- Generic, schema-less, storage of things. Every property of every
+-- thing is stored as a row, with its value in the column matching its
+-- JSON type: numbers and booleans in `numeric_value`, strings in
+-- `text_value`, objects and arrays in `json_value`. The `unit` and
+-- `property_type` (the WebThing `@type`) are copied from the thing
+-- description.
+CREATE TABLE IF NOT EXISTS thing_property (
+    time TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    thing_id TEXT NOT NULL,
+    property_name TEXT NOT NULL,
+
+    -- Values.
+    numeric_value DOUBLE PRECISION,
+    text_value TEXT,
+    json_value JSONB,
+
+    -- Metadata.
+    unit TEXT,
+    property_type TEXT,
+
+    PRIMARY KEY (time, thing_id, property_name)
+);
+
+-- Turn `thing_property` into a hypertable.
+SELECT create_hypertable('thing_property', 'time');
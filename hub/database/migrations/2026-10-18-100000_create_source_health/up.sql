@@ -0,0 +1,12 @@
+-- Health of the sources, i.e. the addresses the aggregator fetches
+-- things from. It contains the time of the last success, the time and
+-- the message of the last error, and the number of consecutive
+-- failures (reset to 0 on success).
+CREATE TABLE IF NOT EXISTS source_health (
+    address TEXT NOT NULL PRIMARY KEY,
+
+    last_success_time TIMESTAMP WITHOUT TIME ZONE,
+    last_error_time TIMESTAMP WITHOUT TIME ZONE,
+    last_error TEXT,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,2 @@
+DROP TABLE vehicle;
+DROP TABLE ev_charging;
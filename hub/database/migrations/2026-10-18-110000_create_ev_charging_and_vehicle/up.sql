@@ -0,0 +1,29 @@
+-- Set of data about the charging station of the Electric Vehicle.
+CREATE TABLE IF NOT EXISTS ev_charging (
+    time TIMESTAMP WITHOUT TIME ZONE NOT NULL PRIMARY KEY,
+
+    is_available BOOLEAN NOT NULL,
+    is_charging BOOLEAN NOT NULL,
+    number_of_phases SMALLINT NOT NULL,
+    power DOUBLE PRECISION NOT NULL,
+    applied_max_current DOUBLE PRECISION NOT NULL,
+    max_current DOUBLE PRECISION NOT NULL,
+    delivered_energy DOUBLE PRECISION NOT NULL,
+    station_temperature DOUBLE PRECISION NOT NULL
+);
+
+-- Turn `ev_charging` into a hypertable.
+SELECT create_hypertable('ev_charging', 'time');
+
+-- Set of data about the vehicle.
+CREATE TABLE IF NOT EXISTS vehicle (
+    time TIMESTAMP WITHOUT TIME ZONE NOT NULL PRIMARY KEY,
+
+    state_of_charge DOUBLE PRECISION NOT NULL,
+    is_charging BOOLEAN NOT NULL,
+    remaining_range DOUBLE PRECISION NOT NULL,
+    odometer DOUBLE PRECISION NOT NULL
+);
+
+-- Turn `vehicle` into a hypertable.
+SELECT create_hypertable('vehicle', 'time');
@@ -0,0 +1,2 @@
+DROP TABLE weather_forecast;
+DROP TABLE weather;
@@ -0,0 +1,49 @@
+-- Set of data about the current weather.
+CREATE TABLE IF NOT EXISTS weather (
+    time TIMESTAMP WITHOUT TIME ZONE NOT NULL PRIMARY KEY,
+
+    clouds DOUBLE PRECISION NOT NULL,
+    temperature DOUBLE PRECISION NOT NULL,
+    apparent_temperature DOUBLE PRECISION NOT NULL,
+    humidity DOUBLE PRECISION NOT NULL,
+    dew_point DOUBLE PRECISION NOT NULL,
+    pressure DOUBLE PRECISION NOT NULL,
+    uv_index DOUBLE PRECISION NOT NULL,
+    visibility DOUBLE PRECISION NOT NULL,
+    wind_degree DOUBLE PRECISION NOT NULL,
+    wind_speed DOUBLE PRECISION NOT NULL,
+    wind_gust DOUBLE PRECISION NOT NULL,
+    rain DOUBLE PRECISION NOT NULL,
+    snow DOUBLE PRECISION NOT NULL,
+    condition INTEGER NOT NULL
+);
+
+-- Turn `weather` into a hypertable.
+SELECT create_hypertable('weather', 'time');
+
+-- Set of hourly weather forecasts. Each forecast is a snapshot: it is
+-- identified by when it has been fetched, and the hour it forecasts.
+CREATE TABLE IF NOT EXISTS weather_forecast (
+    fetched_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    forecast_for TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+
+    clouds DOUBLE PRECISION NOT NULL,
+    temperature DOUBLE PRECISION NOT NULL,
+    apparent_temperature DOUBLE PRECISION NOT NULL,
+    humidity DOUBLE PRECISION NOT NULL,
+    dew_point DOUBLE PRECISION NOT NULL,
+    pressure DOUBLE PRECISION NOT NULL,
+    uv_index DOUBLE PRECISION NOT NULL,
+    visibility DOUBLE PRECISION,
+    wind_degree DOUBLE PRECISION NOT NULL,
+    wind_speed DOUBLE PRECISION NOT NULL,
+    wind_gust DOUBLE PRECISION,
+    rain DOUBLE PRECISION,
+    snow DOUBLE PRECISION,
+    condition INTEGER,
+
+    PRIMARY KEY (fetched_at, forecast_for)
+);
+
+-- Turn `weather_forecast` into a hypertable.
+SELECT create_hypertable('weather_forecast', 'fetched_at');
@@ -0,0 +1,6 @@
+ALTER TABLE electricity_storage DROP COLUMN IF EXISTS health;
+ALTER TABLE electricity_storage DROP COLUMN IF EXISTS state;
+DROP TYPE IF EXISTS battery_state;
+ALTER TABLE air DROP COLUMN IF EXISTS extracted_air_fan_speed;
+ALTER TABLE air DROP COLUMN IF EXISTS supplied_air_fan_speed;
+ALTER TABLE air DROP COLUMN IF EXISTS inside_co2_level;
@@ -0,0 +1,11 @@
+-- Add the CO2 level and the fan speeds to the `air` table.
+ALTER TABLE air ADD COLUMN inside_co2_level DOUBLE PRECISION;
+ALTER TABLE air ADD COLUMN supplied_air_fan_speed DOUBLE PRECISION;
+ALTER TABLE air ADD COLUMN extracted_air_fan_speed DOUBLE PRECISION;
+
+-- Create the `battery_state` enum.
+CREATE TYPE battery_state AS ENUM ('idle', 'charging', 'discharging');
+
+-- Add the state and the state of health to the `electricity_storage` table.
+ALTER TABLE electricity_storage ADD COLUMN state battery_state;
+ALTER TABLE electricity_storage ADD COLUMN health DOUBLE PRECISION;
@@ -0,0 +1,16 @@
+-- Timestamps have always been written in UTC, make it explicit so that
+-- they are not shifted, nor ambiguous around DST changes.
+
+ALTER TABLE air ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE domestic_hot_water ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE electricity_consumption ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE electricity_production ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE electricity_storage ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE ev_charging ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE vehicle ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE weather ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE weather_forecast ALTER COLUMN fetched_at TYPE TIMESTAMP WITH TIME ZONE USING fetched_at AT TIME ZONE 'UTC';
+ALTER TABLE weather_forecast ALTER COLUMN forecast_for TYPE TIMESTAMP WITH TIME ZONE USING forecast_for AT TIME ZONE 'UTC';
+ALTER TABLE thing_property ALTER COLUMN time TYPE TIMESTAMP WITH TIME ZONE USING time AT TIME ZONE 'UTC';
+ALTER TABLE source_health ALTER COLUMN last_success_time TYPE TIMESTAMP WITH TIME ZONE USING last_success_time AT TIME ZONE 'UTC';
+ALTER TABLE source_health ALTER COLUMN last_error_time TYPE TIMESTAMP WITH TIME ZONE USING last_error_time AT TIME ZONE 'UTC';
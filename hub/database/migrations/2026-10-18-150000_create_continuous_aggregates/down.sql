@@ -0,0 +1,10 @@
+DROP MATERIALIZED VIEW IF EXISTS air_daily;
+DROP MATERIALIZED VIEW IF EXISTS domestic_hot_water_daily;
+DROP MATERIALIZED VIEW IF EXISTS electricity_storage_daily;
+DROP MATERIALIZED VIEW IF EXISTS electricity_consumption_daily;
+DROP MATERIALIZED VIEW IF EXISTS electricity_production_daily;
+DROP MATERIALIZED VIEW air_hourly;
+DROP MATERIALIZED VIEW domestic_hot_water_hourly;
+DROP MATERIALIZED VIEW electricity_storage_hourly;
+DROP MATERIALIZED VIEW electricity_consumption_hourly;
+DROP MATERIALIZED VIEW electricity_production_hourly;
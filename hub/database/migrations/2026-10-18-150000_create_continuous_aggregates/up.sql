@@ -0,0 +1,75 @@
+-- Hourly and daily rollups, maintained by Timescale.
+--
+-- Energies are in watt-hour: the average power over one hour is the
+-- energy of this hour. A battery ongoing power is positive when
+-- charging, negative when discharging.
+--
+-- Daily rollups are computed from the hourly ones, with days in the
+-- time zone of the house, which is configurable: they are created by
+-- the aggregator, see `continuous_aggregates::create_daily`.
+--
+-- Rollups are real-time: rows that have not been materialized yet are
+-- aggregated when querying. Existing rows, and rows inserted late
+-- (e.g. replayed from a journal), are materialized by the aggregator,
+-- since refreshing cannot happen inside the migration transaction.
+
+CREATE MATERIALIZED VIEW electricity_production_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket('1 hour', time) AS time,
+    avg(power) AS produced_energy
+FROM electricity_production
+GROUP BY 1
+WITH NO DATA;
+
+CREATE MATERIALIZED VIEW electricity_consumption_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket('1 hour', time) AS time,
+    avg(house_power) AS consumed_energy
+FROM electricity_consumption
+GROUP BY 1
+WITH NO DATA;
+
+CREATE MATERIALIZED VIEW electricity_storage_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket('1 hour', time) AS time,
+    avg(greatest(ongoing_power, 0)) AS charged_energy,
+    avg(greatest(-ongoing_power, 0)) AS discharged_energy,
+    min(state_of_charge) AS min_state_of_charge,
+    max(state_of_charge) AS max_state_of_charge
+FROM electricity_storage
+GROUP BY 1
+WITH NO DATA;
+
+CREATE MATERIALIZED VIEW domestic_hot_water_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket('1 hour', time) AS time,
+    avg(top_of_the_tank_temperature) AS avg_top_of_the_tank_temperature,
+    avg(bottom_of_the_tank_temperature) AS avg_bottom_of_the_tank_temperature
+FROM domestic_hot_water
+GROUP BY 1
+WITH NO DATA;
+
+CREATE MATERIALIZED VIEW air_hourly
+WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+SELECT
+    time_bucket('1 hour', time) AS time,
+    avg(supplied_temperature_after_ground_coupled_heat_exchanger) AS avg_supplied_temperature_after_ground_coupled_heat_exchanger,
+    avg(supplied_temperature_after_heat_recovery_exchanger) AS avg_supplied_temperature_after_heat_recovery_exchanger,
+    avg(extracted_temperature) AS avg_extracted_temperature,
+    avg(discharged_temperature) AS avg_discharged_temperature
+FROM air
+GROUP BY 1
+WITH NO DATA;
+
+-- Refresh policies. The last hour is never materialized, as it is not
+-- complete yet; real-time aggregation covers it.
+
+SELECT add_continuous_aggregate_policy('electricity_production_hourly', start_offset => INTERVAL '3 hours', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '30 minutes');
+SELECT add_continuous_aggregate_policy('electricity_consumption_hourly', start_offset => INTERVAL '3 hours', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '30 minutes');
+SELECT add_continuous_aggregate_policy('electricity_storage_hourly', start_offset => INTERVAL '3 hours', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '30 minutes');
+SELECT add_continuous_aggregate_policy('domestic_hot_water_hourly', start_offset => INTERVAL '3 hours', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '30 minutes');
+SELECT add_continuous_aggregate_policy('air_hourly', start_offset => INTERVAL '3 hours', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '30 minutes');
@@ -0,0 +1,17 @@
+-- Energy counters per day, integrated from the instantaneous powers by
+-- the aggregator. `time` is the start of the day, in the local time
+-- zone of the house, like the `*_daily` continuous aggregates. The
+-- counters only grow during the day. Energies are in watt-hour.
+CREATE TABLE IF NOT EXISTS energy_counter (
+    time TIMESTAMP WITH TIME ZONE NOT NULL PRIMARY KEY,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+
+    produced_energy DOUBLE PRECISION NOT NULL,
+    consumed_energy DOUBLE PRECISION NOT NULL,
+    charged_energy DOUBLE PRECISION NOT NULL,
+    discharged_energy DOUBLE PRECISION NOT NULL
+);
+
+-- Turn `energy_counter` into a hypertable. There is one row per day,
+-- so chunks are big.
+SELECT create_hypertable('energy_counter', 'time', chunk_time_interval => INTERVAL '1 year');
@@ -0,0 +1,16 @@
+-- Property values rejected by the validation rules of the aggregator,
+-- e.g. out of range, or changing too fast, kept for inspection. The
+-- value is stored as is, in JSON, with the reason of the rejection.
+CREATE TABLE IF NOT EXISTS rejected_samples (
+    time TIMESTAMP WITH TIME ZONE NOT NULL,
+    thing_id TEXT NOT NULL,
+    property_name TEXT NOT NULL,
+
+    value JSONB NOT NULL,
+    reason TEXT NOT NULL,
+
+    PRIMARY KEY (time, thing_id, property_name)
+);
+
+-- Turn `rejected_samples` into a hypertable.
+SELECT create_hypertable('rejected_samples', 'time');
@@ -0,0 +1,154 @@
+use diesel::{prelude::*, sql_query, sql_types::Text};
+use std::collections::BTreeMap;
+
+/// Continuous aggregates, with the hypertable they aggregate, in the
+/// order they must be refreshed: daily aggregates are computed from
+/// the hourly ones.
+pub const CONTINUOUS_AGGREGATES: [(&str, &str); 10] = [
+    ("electricity_production_hourly", "electricity_production"),
+    ("electricity_consumption_hourly", "electricity_consumption"),
+    ("electricity_storage_hourly", "electricity_storage"),
+    ("domestic_hot_water_hourly", "domestic_hot_water"),
+    ("air_hourly", "air"),
+    ("electricity_production_daily", "electricity_production"),
+    ("electricity_consumption_daily", "electricity_consumption"),
+    ("electricity_storage_daily", "electricity_storage"),
+    ("domestic_hot_water_daily", "domestic_hot_water"),
+    ("air_daily", "air"),
+];
+
+/// Daily continuous aggregates, with the hourly one they are computed
+/// from, and their columns.
+const DAILY_AGGREGATES: [(&str, &str, &str); 5] = [
+    (
+        "electricity_production_daily",
+        "electricity_production_hourly",
+        "sum(produced_energy) AS produced_energy",
+    ),
+    (
+        "electricity_consumption_daily",
+        "electricity_consumption_hourly",
+        "sum(consumed_energy) AS consumed_energy",
+    ),
+    (
+        "electricity_storage_daily",
+        "electricity_storage_hourly",
+        "sum(charged_energy) AS charged_energy, \
+         sum(discharged_energy) AS discharged_energy, \
+         min(min_state_of_charge) AS min_state_of_charge, \
+         max(max_state_of_charge) AS max_state_of_charge",
+    ),
+    (
+        "domestic_hot_water_daily",
+        "domestic_hot_water_hourly",
+        "avg(avg_top_of_the_tank_temperature) AS avg_top_of_the_tank_temperature, \
+         avg(avg_bottom_of_the_tank_temperature) AS avg_bottom_of_the_tank_temperature",
+    ),
+    (
+        "air_daily",
+        "air_hourly",
+        "avg(avg_supplied_temperature_after_ground_coupled_heat_exchanger) AS avg_supplied_temperature_after_ground_coupled_heat_exchanger, \
+         avg(avg_supplied_temperature_after_heat_recovery_exchanger) AS avg_supplied_temperature_after_heat_recovery_exchanger, \
+         avg(avg_extracted_temperature) AS avg_extracted_temperature, \
+         avg(avg_discharged_temperature) AS avg_discharged_temperature",
+    ),
+];
+
+/// The definition of a continuous aggregate.
+#[derive(QueryableByName)]
+struct Definition {
+    #[sql_type = "Text"]
+    view_definition: String,
+}
+
+/// Create the daily continuous aggregates, with days starting at
+/// midnight in `time_zone`, e.g. `Europe/Zurich`, so that they are not
+/// shifted around DST changes. The existing ones are kept, unless their
+/// days are in another time zone: they are created again then, and
+/// materialized again by the next refresh.
+///
+/// It must be called after the migrations.
+pub fn create_daily(connection: &PgConnection, time_zone: &str) -> QueryResult<()> {
+    let time_zone = time_zone.replace('\'', "''");
+
+    for (continuous_aggregate, hourly_aggregate, columns) in DAILY_AGGREGATES {
+        let definition = sql_query(
+            "SELECT view_definition FROM timescaledb_information.continuous_aggregates WHERE view_name = $1",
+        )
+        .bind::<Text, _>(continuous_aggregate)
+        .get_result::<Definition>(connection)
+        .optional()?;
+
+        // The time zone is quoted in the definition.
+        if definition.is_some_and(|definition| {
+            definition
+                .view_definition
+                .contains(&format!("'{}'", time_zone))
+        }) {
+            continue;
+        }
+
+        println!(
+            "Creating the continuous aggregate `{}`, with days in `{}`",
+            continuous_aggregate, time_zone
+        );
+
+        connection.transaction(|| {
+            sql_query(format!(
+                "DROP MATERIALIZED VIEW IF EXISTS {}",
+                continuous_aggregate
+            ))
+            .execute(connection)?;
+
+            sql_query(format!(
+                "CREATE MATERIALIZED VIEW {} \
+                 WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS \
+                 SELECT time_bucket('1 day', time, '{}') AS time, {} \
+                 FROM {} \
+                 GROUP BY 1 \
+                 WITH NO DATA",
+                continuous_aggregate, time_zone, columns, hourly_aggregate
+            ))
+            .execute(connection)?;
+
+            sql_query(format!(
+                "SELECT add_continuous_aggregate_policy('{}', start_offset => INTERVAL '3 days', end_offset => INTERVAL '1 hour', schedule_interval => INTERVAL '1 hour')",
+                continuous_aggregate
+            ))
+            .execute(connection)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Materialize all the continuous aggregates, except the last hour
+/// which is not complete yet. Timescale only recomputes the buckets
+/// whose rows have changed since the last refresh, so it is cheap,
+/// and it catches rows that the refresh policies miss, like the
+/// existing rows or the rows replayed from the journal.
+///
+/// `drop_after` maps hypertables to their retention: buckets whose
+/// rows have been dropped are not refreshed, otherwise they would be
+/// emptied.
+///
+/// It must not be called inside a transaction.
+pub fn refresh(
+    connection: &PgConnection,
+    drop_after: &BTreeMap<String, String>,
+) -> QueryResult<()> {
+    for (continuous_aggregate, hypertable) in CONTINUOUS_AGGREGATES {
+        let window_start = match drop_after.get(hypertable) {
+            Some(drop_after) => format!("now() - '{}'::interval", drop_after.replace('\'', "''")),
+            None => "NULL".to_string(),
+        };
+
+        sql_query(format!(
+            "CALL refresh_continuous_aggregate('{}', {}, now() - INTERVAL '1 hour')",
+            continuous_aggregate, window_start
+        ))
+        .execute(connection)?;
+    }
+
+    Ok(())
+}
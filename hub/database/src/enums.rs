@@ -0,0 +1,16 @@
+use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Debug, Default)]
+pub enum AirState {
+    #[default]
+    Paused,
+    Running,
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Debug)]
+pub enum BatteryState {
+    Idle,
+    Charging,
+    Discharging,
+}
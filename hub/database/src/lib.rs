@@ -0,0 +1,22 @@
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
+pub mod continuous_aggregates;
+pub mod enums;
+pub mod models;
+pub mod schema;
+
+use diesel::PgConnection;
+use diesel_migrations::RunMigrationsError;
+use std::io;
+
+embed_migrations!();
+
+/// Run the migrations that have not been run yet, so that the
+/// database is at the current schema. Migrations are embedded in the
+/// binary.
+pub fn migrate(connection: &PgConnection) -> Result<(), RunMigrationsError> {
+    embedded_migrations::run_with_output(connection, &mut io::stdout())
+}
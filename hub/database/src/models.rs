@@ -0,0 +1,208 @@
+use super::{enums::*, schema::*};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "electricity_production"]
+pub struct ElectricityProduction {
+    pub time: DateTime<Utc>,
+
+    pub l1_voltage: f64,
+    pub l1_frequency: f64,
+    pub l1_power: f64,
+    pub l1_current: f64,
+
+    pub l2_voltage: f64,
+    pub l2_frequency: f64,
+    pub l2_power: f64,
+    pub l2_current: f64,
+
+    pub l3_voltage: f64,
+    pub l3_frequency: f64,
+    pub l3_power: f64,
+    pub l3_current: f64,
+
+    pub voltage: f64,
+    pub frequency: f64,
+    pub power: f64,
+    pub current: f64,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "electricity_storage"]
+pub struct ElectricityStorage {
+    pub time: DateTime<Utc>,
+
+    pub ongoing_power: f64,
+    pub temperature: f64,
+    pub state_of_charge: f64,
+    pub voltage: f64,
+
+    pub state: Option<BatteryState>,
+    pub health: Option<f64>,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "electricity_consumption"]
+pub struct ElectricityConsumption {
+    pub time: DateTime<Utc>,
+
+    pub house_power: f64,
+    pub house_l1_power: f64,
+    pub house_l2_power: f64,
+    pub house_l3_power: f64,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "domestic_hot_water"]
+pub struct DomesticHotWater {
+    pub time: DateTime<Utc>,
+
+    pub top_of_the_tank_temperature: f64,
+    pub bottom_of_the_tank_temperature: f64,
+    pub wanted_temperature: f64,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "air"]
+pub struct Air {
+    pub time: DateTime<Utc>,
+
+    pub inside_humidity: f64,
+
+    pub supplied_temperature_after_ground_coupled_heat_exchanger: f64,
+    pub supplied_temperature_after_heat_recovery_exchanger: f64,
+    pub extracted_temperature: f64,
+    pub discharged_temperature: f64,
+    pub wanted_temperature: f64,
+
+    pub state: Option<AirState>,
+
+    pub inside_co2_level: Option<f64>,
+    pub supplied_air_fan_speed: Option<f64>,
+    pub extracted_air_fan_speed: Option<f64>,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "ev_charging"]
+pub struct EvCharging {
+    pub time: DateTime<Utc>,
+
+    pub is_available: bool,
+    pub is_charging: bool,
+    pub number_of_phases: i16,
+    pub power: f64,
+    pub applied_max_current: f64,
+    pub max_current: f64,
+    pub delivered_energy: f64,
+    pub station_temperature: f64,
+}
+
+/// Energies of a day, in watt-hour. When inserted, the energies are
+/// added to the existing ones.
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "energy_counter"]
+pub struct EnergyCounter {
+    pub time: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    pub produced_energy: f64,
+    pub consumed_energy: f64,
+    pub charged_energy: f64,
+    pub discharged_energy: f64,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "vehicle"]
+pub struct Vehicle {
+    pub time: DateTime<Utc>,
+
+    pub state_of_charge: f64,
+    pub is_charging: bool,
+    pub remaining_range: f64,
+    pub odometer: f64,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "weather"]
+pub struct Weather {
+    pub time: DateTime<Utc>,
+
+    pub clouds: f64,
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub dew_point: f64,
+    pub pressure: f64,
+    pub uv_index: f64,
+    pub visibility: f64,
+    pub wind_degree: f64,
+    pub wind_speed: f64,
+    pub wind_gust: f64,
+    pub rain: f64,
+    pub snow: f64,
+    pub condition: i32,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "weather_forecast"]
+pub struct WeatherForecast {
+    pub fetched_at: DateTime<Utc>,
+    pub forecast_for: DateTime<Utc>,
+
+    pub clouds: f64,
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub dew_point: f64,
+    pub pressure: f64,
+    pub uv_index: f64,
+    pub visibility: Option<f64>,
+    pub wind_degree: f64,
+    pub wind_speed: f64,
+    pub wind_gust: Option<f64>,
+    pub rain: Option<f64>,
+    pub snow: Option<f64>,
+    pub condition: Option<i32>,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "thing_property"]
+pub struct ThingProperty {
+    pub time: DateTime<Utc>,
+
+    pub thing_id: String,
+    pub property_name: String,
+
+    pub numeric_value: Option<f64>,
+    pub text_value: Option<String>,
+    pub json_value: Option<Value>,
+
+    pub unit: Option<String>,
+    pub property_type: Option<String>,
+}
+
+/// A property value rejected by a validation rule, with the reason.
+#[derive(Insertable, Queryable, QueryableByName, Serialize, Deserialize, Clone, Debug)]
+#[table_name = "rejected_samples"]
+pub struct RejectedSample {
+    pub time: DateTime<Utc>,
+
+    pub thing_id: String,
+    pub property_name: String,
+
+    pub value: Value,
+    pub reason: String,
+}
+
+#[derive(Insertable, Queryable, QueryableByName, Clone, Debug)]
+#[table_name = "source_health"]
+pub struct SourceHealth {
+    pub address: String,
+
+    pub last_success_time: Option<DateTime<Utc>>,
+    pub last_error_time: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: i32,
+}
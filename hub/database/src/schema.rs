@@ -0,0 +1,199 @@
+table! {
+    use crate::enums::AirStateMapping;
+    use diesel::sql_types::*;
+
+    air (time) {
+        time -> Timestamptz,
+        inside_humidity -> Float8,
+        supplied_temperature_after_ground_coupled_heat_exchanger -> Float8,
+        supplied_temperature_after_heat_recovery_exchanger -> Float8,
+        extracted_temperature -> Float8,
+        discharged_temperature -> Float8,
+        wanted_temperature -> Float8,
+        state -> Nullable<AirStateMapping>,
+        inside_co2_level -> Nullable<Float8>,
+        supplied_air_fan_speed -> Nullable<Float8>,
+        extracted_air_fan_speed -> Nullable<Float8>,
+    }
+}
+
+table! {
+    domestic_hot_water (time) {
+        time -> Timestamptz,
+        top_of_the_tank_temperature -> Float8,
+        bottom_of_the_tank_temperature -> Float8,
+        wanted_temperature -> Float8,
+    }
+}
+
+table! {
+    electricity_consumption (time) {
+        time -> Timestamptz,
+        house_power -> Float8,
+        house_l1_power -> Float8,
+        house_l2_power -> Float8,
+        house_l3_power -> Float8,
+    }
+}
+
+table! {
+    electricity_production (time) {
+        time -> Timestamptz,
+        l1_voltage -> Float8,
+        l1_frequency -> Float8,
+        l1_power -> Float8,
+        l1_current -> Float8,
+        l2_voltage -> Float8,
+        l2_frequency -> Float8,
+        l2_power -> Float8,
+        l2_current -> Float8,
+        l3_voltage -> Float8,
+        l3_frequency -> Float8,
+        l3_power -> Float8,
+        l3_current -> Float8,
+        voltage -> Float8,
+        frequency -> Float8,
+        power -> Float8,
+        current -> Float8,
+    }
+}
+
+table! {
+    use crate::enums::BatteryStateMapping;
+    use diesel::sql_types::*;
+
+    electricity_storage (time) {
+        time -> Timestamptz,
+        ongoing_power -> Float8,
+        temperature -> Float8,
+        state_of_charge -> Float8,
+        voltage -> Float8,
+        state -> Nullable<BatteryStateMapping>,
+        health -> Nullable<Float8>,
+    }
+}
+
+table! {
+    energy_counter (time) {
+        time -> Timestamptz,
+        updated_at -> Timestamptz,
+        produced_energy -> Float8,
+        consumed_energy -> Float8,
+        charged_energy -> Float8,
+        discharged_energy -> Float8,
+    }
+}
+
+table! {
+    ev_charging (time) {
+        time -> Timestamptz,
+        is_available -> Bool,
+        is_charging -> Bool,
+        number_of_phases -> Int2,
+        power -> Float8,
+        applied_max_current -> Float8,
+        max_current -> Float8,
+        delivered_energy -> Float8,
+        station_temperature -> Float8,
+    }
+}
+
+table! {
+    rejected_samples (time, thing_id, property_name) {
+        time -> Timestamptz,
+        thing_id -> Text,
+        property_name -> Text,
+        value -> Jsonb,
+        reason -> Text,
+    }
+}
+
+table! {
+    source_health (address) {
+        address -> Text,
+        last_success_time -> Nullable<Timestamptz>,
+        last_error_time -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+        consecutive_failures -> Int4,
+    }
+}
+
+table! {
+    thing_property (time, thing_id, property_name) {
+        time -> Timestamptz,
+        thing_id -> Text,
+        property_name -> Text,
+        numeric_value -> Nullable<Float8>,
+        text_value -> Nullable<Text>,
+        json_value -> Nullable<Jsonb>,
+        unit -> Nullable<Text>,
+        property_type -> Nullable<Text>,
+    }
+}
+
+table! {
+    vehicle (time) {
+        time -> Timestamptz,
+        state_of_charge -> Float8,
+        is_charging -> Bool,
+        remaining_range -> Float8,
+        odometer -> Float8,
+    }
+}
+
+table! {
+    weather (time) {
+        time -> Timestamptz,
+        clouds -> Float8,
+        temperature -> Float8,
+        apparent_temperature -> Float8,
+        humidity -> Float8,
+        dew_point -> Float8,
+        pressure -> Float8,
+        uv_index -> Float8,
+        visibility -> Float8,
+        wind_degree -> Float8,
+        wind_speed -> Float8,
+        wind_gust -> Float8,
+        rain -> Float8,
+        snow -> Float8,
+        condition -> Int4,
+    }
+}
+
+table! {
+    weather_forecast (fetched_at, forecast_for) {
+        fetched_at -> Timestamptz,
+        forecast_for -> Timestamptz,
+        clouds -> Float8,
+        temperature -> Float8,
+        apparent_temperature -> Float8,
+        humidity -> Float8,
+        dew_point -> Float8,
+        pressure -> Float8,
+        uv_index -> Float8,
+        visibility -> Nullable<Float8>,
+        wind_degree -> Float8,
+        wind_speed -> Float8,
+        wind_gust -> Nullable<Float8>,
+        rain -> Nullable<Float8>,
+        snow -> Nullable<Float8>,
+        condition -> Nullable<Int4>,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    air,
+    domestic_hot_water,
+    electricity_consumption,
+    electricity_production,
+    electricity_storage,
+    energy_counter,
+    ev_charging,
+    rejected_samples,
+    source_health,
+    thing_property,
+    vehicle,
+    weather,
+    weather_forecast,
+);
@@ -1,178 +1,605 @@
 use crate::{
     command::AddressWithRefreshRate,
-    database::{self, enums::AirState},
+    discovery,
+    energy::Energy,
+    metrics::Metrics,
+    push::Push,
+    sink::Sink,
+    storage::Row,
+    subscription,
     thing::{generic, identified::*},
+    validation::Validator,
 };
-use diesel::prelude::*;
+use chrono::{DateTime, Utc};
+use hub_database::{
+    enums::{AirState, BatteryState},
+    models,
+};
+use serde_json::Value;
 use std::{
-    collections::HashMap,
-    sync::mpsc::channel,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc,
+    },
     thread,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant},
 };
 
-pub fn aggregate(addresses: Vec<AddressWithRefreshRate>, database_connection: PgConnection) {
+/// Maximum delay between two attempts to fetch an unreachable source.
+const MAXIMUM_BACKOFF: Duration = Duration::from_secs(60 * 5);
+
+/// What a polling thread, or a subscription, sends to the receiving
+/// loop.
+pub enum Message {
+    /// Things have been fetched successfully.
+    Things {
+        address: SocketAddr,
+        things: Vec<generic::Thing>,
+    },
+
+    /// The source has failed to be fetched.
+    Failure {
+        address: SocketAddr,
+        error: String,
+        consecutive_failures: u32,
+    },
+}
+
+/// Where the things come from.
+pub struct Sources {
+    /// Addresses to poll, with their refresh rates.
+    pub addresses: Vec<AddressWithRefreshRate>,
+    /// Refresh rate of the servers discovered over mDNS, if the
+    /// discovery is enabled.
+    pub discovery_refresh_rate: Option<NonZeroU64>,
+    /// Receives the values that things push, if enabled.
+    pub push: Option<Push>,
+}
+
+pub fn aggregate(
+    Sources {
+        addresses,
+        discovery_refresh_rate,
+        push,
+    }: Sources,
+    mut sinks: Vec<Box<dyn Sink>>,
+    metrics: Arc<Metrics>,
+    generic: bool,
+    websocket: bool,
+    mut energy: Energy,
+    mut validator: Validator,
+) {
     let (tx, rx) = channel();
 
+    let start_polling = {
+        let tx = tx.clone();
+        let metrics = metrics.clone();
+
+        move |address, refresh_rate| {
+            let stop = Arc::new(AtomicBool::new(false));
+
+            poll(
+                address,
+                refresh_rate,
+                websocket,
+                tx.clone(),
+                metrics.clone(),
+                stop.clone(),
+            );
+
+            stop
+        }
+    };
+
     for AddressWithRefreshRate {
         address,
         refresh_rate,
     } in addresses.iter().cloned()
     {
-        let tx = tx.clone();
+        start_polling(address, refresh_rate);
+    }
 
-        thread::spawn(move || loop {
-            let mut things = reqwest::blocking::get(format!("http://{}", address))
-                .unwrap()
-                .json::<Vec<generic::Thing>>()
-                .unwrap();
+    if let Some(refresh_rate) = discovery_refresh_rate {
+        let explicit_addresses = addresses.iter().map(|address| address.address).collect();
 
-            for thing in things.iter_mut() {
-                let property_values = reqwest::blocking::get(format!("{}/properties", thing.base))
-                    .unwrap()
-                    .json::<HashMap<String, generic::PropertyValue>>()
-                    .unwrap();
+        if let Err(error) = discovery::discover(explicit_addresses, move |address| {
+            start_polling(address, refresh_rate)
+        }) {
+            eprintln!("Failed to discover the WebThing servers: {}", error);
+        }
+    }
+
+    if let Some(push) = push {
+        if let Err(error) = push.serve(tx.clone()) {
+            eprintln!("Failed to receive the pushed values: {}", error);
+        }
+    }
+
+    // The forecast is refreshed way less often than it is fetched, so
+    // a new snapshot is stored only when it has changed.
+    let mut last_forecast = None;
+    // The current weather is fetched at every poll, but the `weather`
+    // program refreshes it every 30 minutes, so a new row is stored
+    // only when it has changed too.
+    let mut last_weather = None;
+    // The production row needs the 4 PV inverters of a source, but a
+    // subscription sends only the thing that has changed: the last
+    // state of each of them is kept.
+    let mut last_pv_inverters = HashMap::<SocketAddr, PvInverters>::new();
+
+    loop {
+        let time_before_flush = sinks
+            .iter()
+            .map(|sink| sink.time_before_flush())
+            .min()
+            .unwrap_or(Duration::MAX);
+
+        let message = match rx.recv_timeout(time_before_flush) {
+            Ok(message) => message,
 
-                for (property_name, property_value) in thing.properties.iter_mut() {
-                    if let Some(value) = property_values.get(property_name) {
-                        property_value.value.replace(value.clone());
+            // Nothing has been received for a while, flush what has
+            // been buffered so far.
+            Err(RecvTimeoutError::Timeout) => {
+                for sink in sinks.iter_mut() {
+                    if sink.time_before_flush().is_zero() {
+                        sink.flush();
                     }
                 }
+
+                continue;
             }
 
-            tx.send(things).unwrap();
+            // `tx` lives as long as this loop.
+            Err(RecvTimeoutError::Disconnected) => unreachable!(),
+        };
 
-            thread::sleep(Duration::from_secs(refresh_rate.into()));
-        });
-    }
+        let now = Utc::now();
 
-    loop {
-        for _ in 0..addresses.len() {
-            let message = rx
-                .recv()
-                .unwrap()
-                .iter()
-                .map(TryInto::try_into)
-                .collect::<Result<Vec<Thing>, _>>()
-                .unwrap();
-            //dbg!(&message);
-
-            let now = SystemTime::now();
-
-            let mut pv0 = None;
-            let mut pv1 = None;
-            let mut pv2 = None;
-            let mut pv3 = None;
-
-            for thing in message {
-                match thing {
-                    Thing::Battery(battery) => {
-                        diesel::insert_into(database::schema::electricity_storage::table)
-                            .values(&database::models::ElectricityStorage {
-                                time: &now,
-                                ongoing_power: battery.ongoing_power,
-                                temperature: battery.temperature,
-                                state_of_charge: battery.state_of_charge,
-                                voltage: battery.voltage,
-                            })
-                            .execute(&database_connection)
-                            .unwrap();
-                    }
+        let (address, mut things) = match message {
+            Message::Things { address, things } => (address, things),
 
-                    Thing::PvInverterAll(pv_inverter) => {
-                        pv0.replace(pv_inverter);
-                    }
-                    Thing::PvInverter1(pv_inverter) => {
-                        pv1.replace(pv_inverter);
-                    }
-                    Thing::PvInverter2(pv_inverter) => {
-                        pv2.replace(pv_inverter);
-                    }
-                    Thing::PvInverter3(pv_inverter) => {
-                        pv3.replace(pv_inverter);
-                    }
+            Message::Failure {
+                address,
+                error,
+                consecutive_failures,
+            } => {
+                for sink in sinks.iter_mut() {
+                    sink.update_source_health(&address, &now, Err((&error, consecutive_failures)));
+                }
+
+                continue;
+            }
+        };
+
+        for sink in sinks.iter_mut() {
+            sink.update_source_health(&address, &now, Ok(()));
+        }
+
+        let mut rows = Vec::new();
+
+        // Things with a rejected value are not identified: their rows
+        // would be incomplete.
+        let mut rejected_things = HashSet::new();
+
+        for thing in things.iter_mut() {
+            let rejected_samples = validator.validate(thing);
+
+            if !rejected_samples.is_empty() {
+                rejected_things.insert(thing.id.clone());
+                rows.extend(rejected_samples.into_iter().map(Row::RejectedSample));
+            }
+        }
+
+        metrics.update_properties(&things);
+
+        if generic {
+            for thing in things.iter() {
+                rows.extend(generic_rows(thing));
+            }
+        }
+
+        let message = things
+            .iter()
+            .filter(|thing| !rejected_things.contains(&thing.id))
+            .filter_map(|thing| match thing.try_into() {
+                Ok(identified_thing) => Some((thing.fetched_at, identified_thing)),
 
-                    Thing::HousePower(house_power) => {
-                        diesel::insert_into(database::schema::electricity_consumption::table)
-                            .values(&database::models::ElectricityConsumption {
-                                time: &now,
-                                house_power: house_power.power,
-                                house_l1_power: house_power.l1_power,
-                                house_l2_power: house_power.l2_power,
-                                house_l3_power: house_power.l3_power,
-                            })
-                            .execute(&database_connection)
-                            .unwrap();
+                // Things that are not known are simply ignored.
+                Err(Error::Unknown(_)) => None,
+
+                Err(error @ Error::Malformed(_)) => {
+                    metrics.malformed(address);
+
+                    eprintln!(
+                        "Skipping thing `{}` from `{}`: {}",
+                        thing.id, address, error
+                    );
+
+                    None
+                }
+            })
+            .collect::<Vec<(DateTime<Utc>, Thing)>>();
+
+        let pv_inverters = last_pv_inverters.entry(address).or_default();
+        let mut has_pv_inverter_changed = false;
+
+        for (time, thing) in message {
+            match thing {
+                Thing::Battery(battery) => {
+                    let state = match battery.state.as_deref() {
+                        Some("idle") => Some(BatteryState::Idle),
+                        Some("charging") => Some(BatteryState::Charging),
+                        Some("discharging") => Some(BatteryState::Discharging),
+                        None => None,
+                        Some(v) => {
+                            eprintln!(
+                                "Thing `Battery` from `{}`: invalid `state` value, received `{:?}`, ignoring it",
+                                address, v
+                            );
+
+                            None
+                        }
+                    };
+
+                    rows.extend(energy.storage(time, battery.ongoing_power));
+                    rows.push(Row::ElectricityStorage(models::ElectricityStorage {
+                        time,
+                        ongoing_power: battery.ongoing_power,
+                        temperature: battery.temperature,
+                        state_of_charge: battery.state_of_charge,
+                        voltage: battery.voltage,
+                        state,
+                        health: battery.health,
+                    }));
+                }
+
+                Thing::PvInverterAll(pv_inverter) => {
+                    pv_inverters[0] = Some((time, pv_inverter));
+                    has_pv_inverter_changed = true;
+                }
+                Thing::PvInverter1(pv_inverter) => {
+                    pv_inverters[1] = Some((time, pv_inverter));
+                    has_pv_inverter_changed = true;
+                }
+                Thing::PvInverter2(pv_inverter) => {
+                    pv_inverters[2] = Some((time, pv_inverter));
+                    has_pv_inverter_changed = true;
+                }
+                Thing::PvInverter3(pv_inverter) => {
+                    pv_inverters[3] = Some((time, pv_inverter));
+                    has_pv_inverter_changed = true;
+                }
+
+                Thing::HousePower(house_power) => {
+                    rows.extend(energy.consumption(time, house_power.power));
+                    rows.push(Row::ElectricityConsumption(
+                        models::ElectricityConsumption {
+                            time,
+                            house_power: house_power.power,
+                            house_l1_power: house_power.l1_power,
+                            house_l2_power: house_power.l2_power,
+                            house_l3_power: house_power.l3_power,
+                        },
+                    ));
+                }
+
+                Thing::DomesticHotWater(dhw) => {
+                    rows.push(Row::DomesticHotWater(models::DomesticHotWater {
+                        time,
+                        top_of_the_tank_temperature: dhw.top_of_the_tank_temperature,
+                        bottom_of_the_tank_temperature: dhw.bottom_of_the_tank_temperature,
+                        wanted_temperature: dhw.wanted_temperature,
+                    }));
+                }
+
+                Thing::Air(air) => {
+                    let state = match air.state.as_str() {
+                        "paused" => AirState::Paused,
+                        "running" => AirState::Running,
+                        v => {
+                            eprintln!(
+                                "Skipping thing `Air` from `{}`: invalid `state` value, received `{:?}`",
+                                address, v
+                            );
+
+                            continue;
+                        }
+                    };
+
+                    rows.push(Row::Air(models::Air {
+                        time,
+                        state: Some(state),
+                        inside_humidity: air.inside_humidity,
+                        supplied_temperature_after_ground_coupled_heat_exchanger: air
+                            .supplied_temperature_after_ground_coupled_heat_exchanger,
+                        supplied_temperature_after_heat_recovery_exchanger: air
+                            .supplied_temperature_after_heat_recovery_exchanger,
+                        extracted_temperature: air.extracted_temperature,
+                        discharged_temperature: air.discharged_temperature,
+                        wanted_temperature: air.wanted_temperature,
+                        inside_co2_level: air.inside_co2_level,
+                        supplied_air_fan_speed: air.supplied_air_fan_speed,
+                        extracted_air_fan_speed: air.extracted_air_fan_speed,
+                    }));
+                }
+
+                Thing::ChargingStation(charging_station) => {
+                    rows.push(Row::EvCharging(models::EvCharging {
+                        time,
+                        is_available: charging_station.is_available,
+                        is_charging: charging_station.is_charging,
+                        number_of_phases: charging_station.number_of_phases as i16,
+                        power: charging_station.power,
+                        applied_max_current: charging_station.applied_max_current,
+                        max_current: charging_station.max_current,
+                        delivered_energy: charging_station.delivered_energy,
+                        station_temperature: charging_station.station_temperature,
+                    }));
+                }
+
+                Thing::Vehicle(vehicle) => {
+                    rows.push(Row::Vehicle(models::Vehicle {
+                        time,
+                        state_of_charge: vehicle.state_of_charge,
+                        is_charging: vehicle.is_charging,
+                        remaining_range: vehicle.remaining_range,
+                        odometer: vehicle.odometer,
+                    }));
+                }
+
+                Thing::CurrentWeather(weather) => {
+                    if last_weather.as_ref() == Some(&weather) {
+                        continue;
                     }
 
-                    Thing::DomesticHotWater(dhw) => {
-                        diesel::insert_into(database::schema::domestic_hot_water::table)
-                            .values(&database::models::DomesticHotWater {
-                                time: &now,
-                                top_of_the_tank_temperature: dhw.top_of_the_tank_temperature,
-                                bottom_of_the_tank_temperature: dhw.bottom_of_the_tank_temperature,
-                                wanted_temperature: dhw.wanted_temperature,
-                            })
-                            .execute(&database_connection)
-                            .unwrap();
+                    rows.push(Row::Weather(models::Weather {
+                        time,
+                        clouds: weather.clouds,
+                        temperature: weather.temperature,
+                        apparent_temperature: weather.apparent_temperature,
+                        humidity: weather.humidity,
+                        dew_point: weather.dew_point,
+                        pressure: weather.pressure,
+                        uv_index: weather.uv_index,
+                        visibility: weather.visibility,
+                        wind_degree: weather.wind_degree,
+                        wind_speed: weather.wind_speed,
+                        wind_gust: weather.wind_gust,
+                        rain: weather.rain,
+                        snow: weather.snow,
+                        condition: weather.condition as i32,
+                    }));
+
+                    last_weather = Some(weather);
+                }
+
+                Thing::Forecast(forecast) => {
+                    // An empty forecast means the `weather` program has
+                    // failed to fetch it.
+                    if forecast.hourly.is_empty()
+                        || last_forecast.as_ref() == Some(&forecast.hourly)
+                    {
+                        continue;
                     }
 
-                    Thing::Air(air) => {
-                        diesel::insert_into(database::schema::air::table)
-                            .values(&database::models::Air {
-                                time: &now,
-                                state: match air.state.as_str() {
-                                    "paused" => AirState::Paused,
-                                    "running" => AirState::Running,
-                                    v => panic!("Invalid `air.state` value, received `{:?}`", v),
-                                },
-                                inside_humidity: air.inside_humidity,
-                                supplied_temperature_after_ground_coupled_heat_exchanger: air
-                                    .supplied_temperature_after_ground_coupled_heat_exchanger,
-                                supplied_temperature_after_heat_recovery_exchanger: air
-                                    .supplied_temperature_after_heat_recovery_exchanger,
-                                extracted_temperature: air.extracted_temperature,
-                                discharged_temperature: air.discharged_temperature,
-                                wanted_temperature: air.wanted_temperature,
-                            })
-                            .execute(&database_connection)
-                            .unwrap();
+                    for weather in &forecast.hourly {
+                        rows.push(Row::WeatherForecast(models::WeatherForecast {
+                            fetched_at: time,
+                            forecast_for: weather.datetime,
+                            clouds: weather.clouds,
+                            temperature: weather.temperature,
+                            apparent_temperature: weather.apparent_temperature,
+                            humidity: weather.humidity,
+                            dew_point: weather.dew_point,
+                            pressure: weather.pressure,
+                            uv_index: weather.uv_index,
+                            visibility: weather.visibility,
+                            wind_degree: weather.wind_degree,
+                            wind_speed: weather.wind_speed,
+                            wind_gust: weather.wind_gust,
+                            rain: weather.rain.as_ref().map(|rain| rain.one_hour),
+                            snow: weather.snow.as_ref().map(|snow| snow.one_hour),
+                            condition: weather
+                                .conditions
+                                .first()
+                                .map(|condition| condition.id as i32),
+                        }));
                     }
+
+                    last_forecast = Some(forecast.hourly);
                 }
             }
+        }
+
+        match (has_pv_inverter_changed, &*pv_inverters) {
+            (
+                true,
+                [Some((time0, pv0)), Some((time1, pv1)), Some((time2, pv2)), Some((time3, pv3))],
+            ) => {
+                let time = *[time0, time1, time2, time3].into_iter().max().unwrap();
+
+                rows.extend(energy.production(time, pv0.power));
+                rows.push(Row::ElectricityProduction(models::ElectricityProduction {
+                    time,
+
+                    l1_voltage: pv1.voltage,
+                    l1_frequency: pv1.frequency,
+                    l1_power: pv1.power,
+                    l1_current: pv1.current,
+
+                    l2_voltage: pv2.voltage,
+                    l2_frequency: pv2.frequency,
+                    l2_power: pv2.power,
+                    l2_current: pv2.current,
+
+                    l3_voltage: pv3.voltage,
+                    l3_frequency: pv3.frequency,
+                    l3_power: pv3.power,
+                    l3_current: pv3.current,
+
+                    voltage: pv0.voltage,
+                    frequency: pv0.frequency,
+                    power: pv0.power,
+                    current: pv0.current,
+                }));
+            }
+
+            _ => (),
+        }
+
+        for sink in sinks.iter_mut() {
+            sink.store(&rows);
+        }
+    }
+}
+
+/// The last state of the PV inverters of a source, with their fetch
+/// times: all of them, then the ones of the phases 1, 2 and 3.
+type PvInverters = [Option<(DateTime<Utc>, PvInverter)>; 4];
+
+/// Turn all the properties of a thing into rows of the
+/// `thing_property` table, whatever the thing is.
+fn generic_rows(thing: &generic::Thing) -> impl Iterator<Item = Row> + '_ {
+    thing
+        .properties
+        .iter()
+        .filter_map(move |(property_name, property)| {
+            let value = property.value.as_ref()?;
+
+            let (numeric_value, text_value, json_value) = match value {
+                Value::Null => (None, None, None),
+                Value::Bool(value) => (Some(if *value { 1. } else { 0. }), None, None),
+                Value::Number(value) => (value.as_f64(), None, None),
+                Value::String(value) => (None, Some(value.clone()), None),
+                Value::Array(_) | Value::Object(_) => (None, None, Some(value.clone())),
+            };
+
+            Some(Row::ThingProperty(models::ThingProperty {
+                time: thing.fetched_at,
+                thing_id: thing.id.clone(),
+                property_name: property_name.clone(),
+                numeric_value,
+                text_value,
+                json_value,
+                unit: property.unit.clone(),
+                property_type: property
+                    .r#type
+                    .as_ref()
+                    .map(|r#type| r#type.as_str().to_string()),
+            }))
+        })
+}
+
+/// Fetch the things of `address` every `refresh_rate` seconds, or
+/// subscribe to them, in a thread, and send them to `tx`, until `stop`
+/// is set.
+fn poll(
+    address: SocketAddr,
+    refresh_rate: NonZeroU64,
+    websocket: bool,
+    tx: Sender<Message>,
+    metrics: Arc<Metrics>,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        let mut consecutive_failures = 0u32;
+
+        while !stop.load(Ordering::Relaxed) {
+            let fetch_started_at = Instant::now();
+            let fetched = fetch_things(&address);
+
+            match &fetched {
+                Ok(_) => metrics.fetched(address, fetch_started_at.elapsed()),
+                Err(_) => metrics.failed(address),
+            }
+
+            let next_fetch_in = match fetched {
+                Ok(things) if websocket => {
+                    consecutive_failures = 0;
+
+                    tx.send(Message::Things {
+                        address,
+                        things: things.clone(),
+                    })
+                    .unwrap();
+
+                    // Blocks as long as the WebSockets are alive, then
+                    // falls back to polling until the next subscription.
+                    let error = subscription::subscribe(address, things, &tx, &metrics, &stop);
+
+                    eprintln!(
+                        "Subscription to `{}` has stopped, falling back to polling: {}",
+                        address, error
+                    );
+
+                    Duration::from_secs(refresh_rate.into())
+                }
+
+                Ok(things) => {
+                    consecutive_failures = 0;
+
+                    tx.send(Message::Things { address, things }).unwrap();
+
+                    Duration::from_secs(refresh_rate.into())
+                }
+
+                Err(error) => {
+                    consecutive_failures = consecutive_failures.saturating_add(1);
+                    let backoff = backoff(consecutive_failures);
+
+                    eprintln!(
+                        "Failed to fetch things from `{}` ({} consecutive failure(s)), retrying in {:?}: {}",
+                        address, consecutive_failures, backoff, error
+                    );
+
+                    tx.send(Message::Failure {
+                        address,
+                        error: error.to_string(),
+                        consecutive_failures,
+                    })
+                    .unwrap();
+
+                    backoff
+                }
+            };
+
+            thread::sleep(next_fetch_in);
+        }
+    });
+}
+
+/// Fetch all the things of a source, with their property values.
+fn fetch_things(address: &SocketAddr) -> Result<Vec<generic::Thing>, reqwest::Error> {
+    let mut things = reqwest::blocking::get(format!("http://{}", address))?
+        .error_for_status()?
+        .json::<Vec<generic::Thing>>()?;
+
+    for thing in things.iter_mut() {
+        let property_values = reqwest::blocking::get(format!("{}/properties", thing.base))?
+            .error_for_status()?
+            .json::<HashMap<String, generic::PropertyValue>>()?;
 
-            match (pv0, pv1, pv2, pv3) {
-                (Some(pv0), Some(pv1), Some(pv2), Some(pv3)) => {
-                    diesel::insert_into(database::schema::electricity_production::table)
-                        .values(&database::models::ElectricityProduction {
-                            time: &now,
-
-                            l1_voltage: pv1.voltage,
-                            l1_frequency: pv1.frequency,
-                            l1_power: pv1.power,
-                            l1_current: pv1.current,
-
-                            l2_voltage: pv2.voltage,
-                            l2_frequency: pv2.frequency,
-                            l2_power: pv2.power,
-                            l2_current: pv2.current,
-
-                            l3_voltage: pv3.voltage,
-                            l3_frequency: pv3.frequency,
-                            l3_power: pv3.power,
-                            l3_current: pv3.current,
-
-                            voltage: pv0.voltage,
-                            frequency: pv0.frequency,
-                            power: pv0.power,
-                            current: pv0.current,
-                        })
-                        .execute(&database_connection)
-                        .unwrap();
-                }
-
-                _ => (),
+        for (property_name, property_value) in thing.properties.iter_mut() {
+            if let Some(value) = property_values.get(property_name) {
+                property_value.value.replace(value.clone());
             }
         }
+
+        thing.fetched_at = Utc::now();
     }
+
+    Ok(things)
+}
+
+/// Compute the delay before the next attempt to fetch a source, based
+/// on its number of consecutive failures: 1s, 2s, 4s, 8s… up to
+/// `MAXIMUM_BACKOFF`.
+fn backoff(consecutive_failures: u32) -> Duration {
+    Duration::from_secs(1)
+        .checked_mul(2u32.saturating_pow(consecutive_failures.saturating_sub(1)))
+        .map_or(MAXIMUM_BACKOFF, |backoff| backoff.min(MAXIMUM_BACKOFF))
 }
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     net::{AddrParseError, SocketAddr},
     num::{NonZeroU64, ParseIntError},
+    path::PathBuf,
     str::FromStr,
 };
 use structopt::StructOpt;
@@ -25,9 +26,152 @@ pub struct Options {
     #[structopt(short = "d", long)]
     pub database_url: Option<String>,
 
+    /// Maximum number of seconds rows are buffered before being
+    /// inserted in the database. It cannot be zero.
+    #[structopt(short = "f", long)]
+    pub flush_interval: Option<NonZeroU64>,
+
+    /// Maximum number of rows buffered before being inserted in the
+    /// database.
+    #[structopt(short = "b", long)]
+    pub batch_size: Option<usize>,
+
+    /// The directory where rows are journaled when the database is
+    /// unreachable.
+    #[structopt(short = "j", long)]
+    pub journal_directory: Option<PathBuf>,
+
+    /// Stores every property of every thing in the `thing_property`
+    /// table, in addition to the identified things.
+    #[structopt(short = "g", long)]
+    pub generic: bool,
+
+    /// Subscribes to the WebSocket of every thing to record the
+    /// property changes as they arrive, instead of polling at the
+    /// refresh rate. Polling is used as a fallback when a WebSocket
+    /// drops.
+    #[structopt(short = "w", long)]
+    pub websocket: bool,
+
+    /// Discovers the WebThing servers advertised over mDNS, and
+    /// collects their events. Servers that vanish are not polled
+    /// anymore. Addresses given explicitly keep their own refresh
+    /// rates.
+    #[structopt(short = "s", long)]
+    pub discover: bool,
+
+    /// Refresh rate of the discovered servers, in seconds.
+    #[structopt(long)]
+    pub discovery_refresh_rate: Option<NonZeroU64>,
+
+    /// Address to serve the Prometheus metrics on, at `/metrics`,
+    /// e.g. `127.0.0.1:9100`. Metrics are not served by default.
+    #[structopt(short = "m", long)]
+    pub metrics_address: Option<SocketAddr>,
+
     /// Prints the configuration path and exit.
     #[structopt(short = "c", long)]
     pub print_config_path: bool,
+
+    /// The sub-command. Without one, events are aggregated.
+    #[structopt(subcommand)]
+    pub kind: Option<CommandKind>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum CommandKind {
+    /// Run the database migrations that have not been run yet, and
+    /// exit.
+    Migrate,
+
+    /// Manage the compression and retention policies of the
+    /// hypertables.
+    Policies(PoliciesCommand),
+
+    /// Export rows of the hypertables, one file per table.
+    Export {
+        /// Tables to export. All the tables by default.
+        tables: Vec<String>,
+
+        /// Start of the time range (included), e.g. `2026-10-01` or
+        /// `2026-10-01T12:00:00Z`.
+        #[structopt(long)]
+        from: String,
+
+        /// End of the time range (excluded). Now by default.
+        #[structopt(long)]
+        to: Option<String>,
+
+        /// Aggregates rows per bucket of this size, e.g. `1 hour` or
+        /// `1 day`. Rows are exported as is by default.
+        #[structopt(long)]
+        bucket: Option<String>,
+
+        /// The format of the files, `csv` or `parquet`.
+        #[structopt(long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// The directory where the files are written.
+        #[structopt(short = "o", long, default_value = ".")]
+        output: PathBuf,
+    },
+
+    /// Import CSV files exported by the Victron VRM portal in the
+    /// electricity tables. Rows already in the database are skipped.
+    Import {
+        /// The CSV files.
+        #[structopt(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum PoliciesCommand {
+    /// Apply the policies of the configuration file to the database.
+    Apply {
+        /// Tables to apply the policies to. All the tables of the
+        /// configuration file by default.
+        tables: Vec<String>,
+    },
+
+    /// List the policies of the database.
+    List,
+
+    /// Remove policies from the database.
+    Remove {
+        /// Tables to remove the policies from. All the tables of the
+        /// configuration file by default.
+        tables: Vec<String>,
+
+        /// Removes the compression policies only.
+        #[structopt(long)]
+        compression: bool,
+
+        /// Removes the retention policies only.
+        #[structopt(long)]
+        retention: bool,
+    },
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "parquet" => Ok(Self::Parquet),
+            _ => Err(format!(
+                "The format must be `csv` or `parquet`, given `{}`",
+                s
+            )),
+        }
+    }
 }
 
 impl FromStr for AddressWithRefreshRate {
@@ -1,12 +1,262 @@
 use crate::command::AddressWithRefreshRate;
+use chrono_tz::Tz;
 use directories_next::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Configuration {
     pub addresses: Vec<AddressWithRefreshRate>,
     pub database_url: String,
+    #[serde(default)]
+    pub generic: bool,
+    #[serde(default)]
+    pub websocket: bool,
+    #[serde(default)]
+    pub discover: bool,
+    #[serde(default = "default_discovery_refresh_rate")]
+    pub discovery_refresh_rate: NonZeroU64,
+    #[serde(default)]
+    pub journal_directory: Option<PathBuf>,
+    #[serde(default = "default_flush_interval")]
+    pub flush_interval: NonZeroU64,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub metrics_address: Option<SocketAddr>,
+    /// The time zone of the house, where days of the energy counters
+    /// and of the daily continuous aggregates start.
+    #[serde(default = "default_time_zone")]
+    pub time_zone: Tz,
+    #[serde(default)]
+    pub push: Option<PushConfiguration>,
+    #[serde(default)]
+    pub sinks: SinksConfiguration,
+    #[serde(default)]
+    pub import: ImportConfiguration,
+    #[serde(default = "default_policies")]
+    pub policies: BTreeMap<String, Policy>,
+    /// Validation rules of the property values, indexed by thing ID,
+    /// then by property name.
+    #[serde(default = "default_validation")]
+    pub validation: BTreeMap<String, BTreeMap<String, ValidationRule>>,
+}
+
+/// How things push their values, instead of being polled.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushConfiguration {
+    /// Address to listen on, e.g. `0.0.0.0:8095`.
+    pub address: SocketAddr,
+    /// The token that things send in the `Authorization: Bearer`
+    /// header. It cannot be empty.
+    #[serde(deserialize_with = "deserialize_token")]
+    pub token: String,
+    /// Files of the descriptions of the things, as served by a
+    /// WebThing server.
+    pub things: Vec<PathBuf>,
+}
+
+/// Where the rows go. Sinks can be combined.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SinksConfiguration {
+    /// Inserts the rows in the PostgreSQL database.
+    #[serde(default = "default_postgresql")]
+    pub postgresql: bool,
+    /// Publishes the values to an MQTT broker.
+    #[serde(default)]
+    pub mqtt: Option<MqttConfiguration>,
+    /// Writes the rows to InfluxDB.
+    #[serde(default)]
+    pub influxdb: Option<InfluxDbConfiguration>,
+    /// Appends the rows to a JSON lines file.
+    #[serde(default)]
+    pub json_lines: Option<JsonLinesConfiguration>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MqttConfiguration {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Prefix of all the topics, e.g. `la-maison-vivante` gives
+    /// `la-maison-vivante/air/inside_humidity`.
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InfluxDbConfiguration {
+    /// The write URL, e.g. `http://localhost:8086/api/v2/write?org=home&bucket=house`
+    /// for InfluxDB 2, or `http://localhost:8086/write?db=house` for
+    /// InfluxDB 1.
+    pub url: String,
+    /// The API token of InfluxDB 2.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonLinesConfiguration {
+    pub path: PathBuf,
+}
+
+/// How the `import` subcommand finds the columns of the CSV files.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ImportConfiguration {
+    /// Words of the headers, indexed by `<table>.<column>`, e.g.
+    /// `"electricity_storage.temperature" = "battery temperature"`.
+    /// They add to, or replace, the headers of the VRM exports.
+    #[serde(default)]
+    pub columns: BTreeMap<String, String>,
+}
+
+/// Compression and retention policies of a hypertable. Durations are
+/// PostgreSQL intervals, e.g. `7 days`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Policy {
+    /// Chunks older than this are compressed.
+    #[serde(default)]
+    pub compress_after: Option<String>,
+    /// Chunks older than this are dropped.
+    #[serde(default)]
+    pub drop_after: Option<String>,
+}
+
+fn default_discovery_refresh_rate() -> NonZeroU64 {
+    NonZeroU64::new(10).unwrap()
+}
+
+/// Rules a numeric property value must follow to be stored. Rejected
+/// values go to the `rejected_samples` table instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ValidationRule {
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub maximum: Option<f64>,
+    /// Maximum change per second since the last accepted value.
+    #[serde(default)]
+    pub maximum_change_per_second: Option<f64>,
+    /// Rejects `0` when the last accepted value is not `0`, e.g. when
+    /// a failed read is reported as `0`.
+    #[serde(default)]
+    pub reject_zero_after_non_zero: bool,
+}
+
+fn default_flush_interval() -> NonZeroU64 {
+    NonZeroU64::new(10).unwrap()
+}
+
+/// An empty token would let `Authorization: Bearer ` authenticate.
+fn deserialize_token<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let token = String::deserialize(deserializer)?;
+
+    if token.trim().is_empty() {
+        return Err(de::Error::custom("the push token cannot be empty"));
+    }
+
+    Ok(token)
+}
+
+fn default_batch_size() -> usize {
+    1000
+}
+
+fn default_time_zone() -> Tz {
+    Tz::Europe__Zurich
+}
+
+fn default_postgresql() -> bool {
+    true
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "la-maison-vivante".to_string()
+}
+
+impl Default for SinksConfiguration {
+    fn default() -> Self {
+        Self {
+            postgresql: default_postgresql(),
+            mqtt: None,
+            influxdb: None,
+            json_lines: None,
+        }
+    }
+}
+
+/// Raw data are compressed after a week, and dropped after a year.
+/// Continuous aggregates are kept.
+fn default_policies() -> BTreeMap<String, Policy> {
+    [
+        "air",
+        "domestic_hot_water",
+        "electricity_consumption",
+        "electricity_production",
+        "electricity_storage",
+        "ev_charging",
+        "rejected_samples",
+        "thing_property",
+        "vehicle",
+        "weather",
+        "weather_forecast",
+    ]
+    .into_iter()
+    .map(|table| {
+        (
+            table.to_string(),
+            Policy {
+                compress_after: Some("7 days".to_string()),
+                drop_after: Some("1 year".to_string()),
+            },
+        )
+    })
+    .collect()
+}
+
+/// The state of charge of the battery is a percentage, which is never
+/// `0` in practice, and the PV inverters cannot produce a negative
+/// power.
+fn default_validation() -> BTreeMap<String, BTreeMap<String, ValidationRule>> {
+    let state_of_charge = ValidationRule {
+        minimum: Some(0.),
+        maximum: Some(100.),
+        reject_zero_after_non_zero: true,
+        ..ValidationRule::default()
+    };
+    let power = ValidationRule {
+        minimum: Some(0.),
+        ..ValidationRule::default()
+    };
+
+    [(
+        "urn:dev:ops:battery".to_string(),
+        [("state_of_charge".to_string(), state_of_charge)].into(),
+    )]
+    .into_iter()
+    .chain((0..=3).map(|nth| {
+        (
+            format!("urn:dev:ops:pv-inverter-{}", nth),
+            [("power".to_string(), power.clone())].into(),
+        )
+    }))
+    .collect()
 }
 
 impl Default for Configuration {
@@ -14,6 +264,20 @@ impl Default for Configuration {
         Self {
             addresses: vec![],
             database_url: String::new(),
+            generic: false,
+            websocket: false,
+            discover: false,
+            discovery_refresh_rate: default_discovery_refresh_rate(),
+            journal_directory: None,
+            flush_interval: default_flush_interval(),
+            batch_size: default_batch_size(),
+            metrics_address: None,
+            push: None,
+            time_zone: default_time_zone(),
+            sinks: SinksConfiguration::default(),
+            import: ImportConfiguration::default(),
+            policies: default_policies(),
+            validation: default_validation(),
         }
     }
 }
@@ -33,6 +297,13 @@ pub fn get_path() -> Result<PathBuf, &'static str> {
     Ok(path)
 }
 
+pub fn get_journal_directory() -> Result<PathBuf, &'static str> {
+    let project = ProjectDirs::from("rs", "", "hub-event-aggregator")
+        .ok_or("Failed to find the configuration project directory.")?;
+
+    Ok(project.data_dir().join("journal"))
+}
+
 pub fn load(path: impl AsRef<Path>) -> Result<Configuration, confy::ConfyError> {
     confy::load_path(path)
 }
@@ -1,7 +0,0 @@
-use diesel_derive_enum::DbEnum;
-
-#[derive(DbEnum, Debug)]
-pub enum AirState {
-    Paused,
-    Running,
-}
@@ -1,3 +0,0 @@
-pub mod enums;
-pub mod models;
-pub mod schema;
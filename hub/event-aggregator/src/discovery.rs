@@ -0,0 +1,89 @@
+use mdns_sd::{ServiceDaemon, ServiceEvent};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+/// The mDNS service type advertised by the WebThing servers.
+const SERVICE_TYPE: &str = "_webthing._tcp.local.";
+
+/// Browse the WebThing servers advertised over mDNS, in a thread.
+///
+/// `start_polling` is called with the address of every newly seen
+/// server, and returns a flag that stops polling it once set. The flag
+/// is set when the server vanishes, or moves to another address.
+/// Servers listening on `explicit_addresses` are ignored: they are
+/// already polled, at their own refresh rate.
+pub fn discover<F>(
+    explicit_addresses: HashSet<SocketAddr>,
+    mut start_polling: F,
+) -> Result<(), mdns_sd::Error>
+where
+    F: FnMut(SocketAddr) -> Arc<AtomicBool> + Send + 'static,
+{
+    let daemon = ServiceDaemon::new()?;
+    let receiver = daemon.browse(SERVICE_TYPE)?;
+
+    thread::spawn(move || {
+        // The daemon stops browsing when it is dropped.
+        let _daemon = daemon;
+
+        // Polled servers, indexed by their mDNS names.
+        let mut servers = HashMap::<String, (SocketAddr, Arc<AtomicBool>)>::new();
+
+        while let Ok(event) = receiver.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(service) => {
+                    let name = service.get_fullname().to_string();
+
+                    // IPv4 is preferred, as the servers don't always
+                    // listen on IPv6.
+                    let address = match service.get_addresses().iter().min_by_key(|ip| ip.is_ipv6())
+                    {
+                        Some(ip) => SocketAddr::new(*ip, service.get_port()),
+                        None => continue,
+                    };
+
+                    if explicit_addresses.contains(&address)
+                        || matches!(servers.get(&name), Some((polled_address, _)) if *polled_address == address)
+                    {
+                        continue;
+                    }
+
+                    if let Some((polled_address, stop)) = servers.remove(&name) {
+                        println!(
+                            "WebThing server `{}` has moved from `{}` to `{}`",
+                            name, polled_address, address
+                        );
+
+                        stop.store(true, Ordering::Relaxed);
+                    } else {
+                        println!("Discovered WebThing server `{}` at `{}`", name, address);
+                    }
+
+                    servers.insert(name, (address, start_polling(address)));
+                }
+
+                ServiceEvent::ServiceRemoved(_, name) => {
+                    if let Some((address, stop)) = servers.remove(&name) {
+                        println!(
+                            "WebThing server `{}` at `{}` has vanished, it is not polled anymore",
+                            name, address
+                        );
+
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+
+                _ => (),
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,446 @@
+use crate::storage::Row;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use hub_database::models;
+use std::time::Duration;
+
+/// Samples further apart than this are not integrated: the power in
+/// between is unknown (e.g. the source was unreachable), and guessing
+/// it would make the counters wrong.
+const MAXIMUM_GAP: Duration = Duration::from_secs(15 * 60);
+
+/// A power sample, in watt.
+type Sample = (DateTime<Utc>, f64);
+
+/// Integrate the powers over time, between consecutive samples, into
+/// energy counters per day. Each integration gives rows of the
+/// `energy_counter` table with the energy to add to the counters of
+/// the day.
+pub struct Energy {
+    time_zone: Tz,
+    production: Option<Sample>,
+    consumption: Option<Sample>,
+    storage: Option<Sample>,
+}
+
+impl Energy {
+    /// Days start at midnight in `time_zone`.
+    pub fn new(time_zone: Tz) -> Self {
+        Self {
+            time_zone,
+            production: None,
+            consumption: None,
+            storage: None,
+        }
+    }
+
+    /// A sample of the production power, from the PV inverters.
+    pub fn production(&mut self, time: DateTime<Utc>, power: f64) -> Vec<Row> {
+        let previous = advance(&mut self.production, (time, power));
+
+        self.integrate(previous, (time, power), average_power)
+            .into_iter()
+            .map(|(day, energy)| {
+                Row::EnergyCounter(models::EnergyCounter {
+                    produced_energy: energy,
+                    ..counter(day, time)
+                })
+            })
+            .collect()
+    }
+
+    /// A sample of the house power.
+    pub fn consumption(&mut self, time: DateTime<Utc>, power: f64) -> Vec<Row> {
+        let previous = advance(&mut self.consumption, (time, power));
+
+        self.integrate(previous, (time, power), average_power)
+            .into_iter()
+            .map(|(day, energy)| {
+                Row::EnergyCounter(models::EnergyCounter {
+                    consumed_energy: energy,
+                    ..counter(day, time)
+                })
+            })
+            .collect()
+    }
+
+    /// A sample of the battery ongoing power, positive when charging,
+    /// negative when discharging.
+    pub fn storage(&mut self, time: DateTime<Utc>, ongoing_power: f64) -> Vec<Row> {
+        let previous = advance(&mut self.storage, (time, ongoing_power));
+
+        let charged = self.integrate(previous, (time, ongoing_power), average_positive_power);
+        let discharged = self.integrate(previous, (time, ongoing_power), |from, to| {
+            average_positive_power(-from, -to)
+        });
+
+        // Both are split on the same days.
+        charged
+            .into_iter()
+            .zip(discharged)
+            .map(|((day, charged_energy), (_, discharged_energy))| {
+                Row::EnergyCounter(models::EnergyCounter {
+                    charged_energy,
+                    discharged_energy,
+                    ..counter(day, time)
+                })
+            })
+            .collect()
+    }
+
+    /// Integrate the power between the previous sample and `sample`,
+    /// with `average_power_of` the average power between both, e.g. the
+    /// trapezoidal rule. The energy is split between the days the
+    /// interval spans, proportionally to the time spent in each day.
+    /// Days are identified by their start.
+    fn integrate(
+        &self,
+        previous: Option<Sample>,
+        (to, power): Sample,
+        average_power_of: fn(f64, f64) -> f64,
+    ) -> Vec<(DateTime<Utc>, f64)> {
+        let (from, previous_power) = match previous {
+            Some(previous) => previous,
+            None => return Vec::new(),
+        };
+
+        let elapsed = match (to - from).to_std() {
+            Ok(elapsed) if elapsed.is_zero() => return Vec::new(),
+            Ok(elapsed) if elapsed <= MAXIMUM_GAP => elapsed,
+
+            // Samples in the wrong order are ignored too.
+            _ => {
+                eprintln!(
+                    "Power is unknown between {} and {}, the energy is not counted",
+                    from, to
+                );
+
+                return Vec::new();
+            }
+        };
+
+        // Watt × hour = watt-hour.
+        let energy = average_power_of(previous_power, power) * elapsed.as_secs_f64() / 3600.;
+
+        let mut energies = Vec::new();
+        let mut start = from;
+
+        while start < to {
+            let date = start.with_timezone(&self.time_zone).date_naive();
+            let end = date
+                .succ_opt()
+                .map(|next_date| self.midnight(next_date))
+                .unwrap_or(to)
+                .min(to);
+
+            let share = (end - start).num_milliseconds() as f64
+                / (to - from).num_milliseconds().max(1) as f64;
+
+            energies.push((self.midnight(date), energy * share));
+
+            start = end;
+        }
+
+        energies
+    }
+
+    /// The start of a day. If midnight doesn't exist (a DST change at
+    /// midnight), the day starts one hour later.
+    fn midnight(&self, date: NaiveDate) -> DateTime<Utc> {
+        [0, 1]
+            .into_iter()
+            .filter_map(|hour| date.and_hms_opt(hour, 0, 0))
+            .find_map(|time| self.time_zone.from_local_datetime(&time).earliest())
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()))
+    }
+}
+
+/// Replace the last sample with `sample`, unless `sample` is not newer,
+/// and return the previous last sample. A sample in the wrong order
+/// must not become the last one, otherwise the next sample would be
+/// integrated again over a time that has already been counted.
+fn advance(last: &mut Option<Sample>, sample: Sample) -> Option<Sample> {
+    let previous = *last;
+
+    if previous.is_none_or(|(time, _)| sample.0 > time) {
+        *last = Some(sample);
+    }
+
+    previous
+}
+
+/// The average of a power going linearly from `from` to `to`, i.e. the
+/// trapezoidal rule.
+fn average_power(from: f64, to: f64) -> f64 {
+    (from + to) / 2.
+}
+
+/// The average of the positive part of a power going linearly from
+/// `from` to `to`: when the sign changes, only the part of the interval
+/// before, or after, the zero crossing counts.
+fn average_positive_power(from: f64, to: f64) -> f64 {
+    match (from > 0., to > 0.) {
+        (true, true) => average_power(from, to),
+        (false, false) => 0.,
+        // The power is positive during `from / (from - to)` of the
+        // interval, with an average of `from / 2`.
+        (true, false) => from * from / (from - to) / 2.,
+        (false, true) => to * to / (to - from) / 2.,
+    }
+}
+
+/// Counters of a day, all set to zero.
+fn counter(day: DateTime<Utc>, updated_at: DateTime<Utc>) -> models::EnergyCounter {
+    models::EnergyCounter {
+        time: day,
+        updated_at,
+        produced_energy: 0.,
+        consumed_energy: 0.,
+        charged_energy: 0.,
+        discharged_energy: 0.,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn counters(rows: Vec<Row>) -> Vec<models::EnergyCounter> {
+        rows.into_iter()
+            .map(|row| match row {
+                Row::EnergyCounter(counter) => counter,
+                row => panic!("unexpected row {:?}", row),
+            })
+            .collect()
+    }
+
+    /// The produced energies, indexed by the start of their days.
+    fn produced(rows: Vec<Row>) -> Vec<(DateTime<Utc>, f64)> {
+        counters(rows)
+            .into_iter()
+            .map(|counter| (counter.time, counter.produced_energy))
+            .collect()
+    }
+
+    fn assert_energies(energies: &[(DateTime<Utc>, f64)], expected: &[(&str, f64)]) {
+        assert_eq!(energies.len(), expected.len(), "{:?}", energies);
+
+        for ((day, energy), (expected_day, expected_energy)) in energies.iter().zip(expected) {
+            assert_eq!(*day, utc(expected_day));
+            assert!(
+                (energy - expected_energy).abs() < 1e-9,
+                "{} instead of {}",
+                energy,
+                expected_energy
+            );
+        }
+    }
+
+    #[test]
+    fn test_trapezoidal_integration() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+
+        // The first sample has nothing to be integrated with.
+        assert!(energy
+            .production(utc("2026-06-01T10:00:00Z"), 1000.)
+            .is_empty());
+
+        // (1000 W + 2000 W) / 2 × 1 min = 25 Wh.
+        let rows = energy.production(utc("2026-06-01T10:01:00Z"), 2000.);
+        let counter = &counters(rows.clone())[0];
+
+        assert_eq!(counter.updated_at, utc("2026-06-01T10:01:00Z"));
+        assert_eq!(counter.consumed_energy, 0.);
+        assert_eq!(counter.charged_energy, 0.);
+        assert_eq!(counter.discharged_energy, 0.);
+        assert_energies(&produced(rows), &[("2026-05-31T22:00:00Z", 25.)]);
+
+        // Powers are integrated separately.
+        assert!(energy
+            .consumption(utc("2026-06-01T10:01:00Z"), 500.)
+            .is_empty());
+        assert_energies(
+            &counters(energy.consumption(utc("2026-06-01T10:02:00Z"), 500.))
+                .into_iter()
+                .map(|counter| (counter.time, counter.consumed_energy))
+                .collect::<Vec<_>>(),
+            &[("2026-05-31T22:00:00Z", 500. / 60.)],
+        );
+
+        // A sample at the same time adds nothing.
+        assert!(energy
+            .production(utc("2026-06-01T10:01:00Z"), 2000.)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_maximum_gap() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+
+        energy.production(utc("2026-06-01T10:00:00Z"), 1000.);
+
+        // Exactly the maximum gap is integrated.
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T10:15:00Z"), 1000.)),
+            &[("2026-05-31T22:00:00Z", 250.)],
+        );
+
+        // A longer gap is not…
+        assert!(energy
+            .production(utc("2026-06-01T10:30:01Z"), 1000.)
+            .is_empty());
+
+        // … but the integration resumes from the last sample.
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T10:31:01Z"), 3000.)),
+            &[("2026-05-31T22:00:00Z", 2000. / 60.)],
+        );
+
+        // Samples in the wrong order are not integrated either.
+        assert!(energy
+            .production(utc("2026-06-01T10:30:00Z"), 1000.)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sample_in_the_wrong_order() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+
+        energy.production(utc("2026-06-01T10:00:00Z"), 1000.);
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T10:02:00Z"), 1000.)),
+            &[("2026-05-31T22:00:00Z", 2000. / 60.)],
+        );
+
+        // A late sample is ignored…
+        assert!(energy
+            .production(utc("2026-06-01T10:01:00Z"), 1000.)
+            .is_empty());
+
+        // … and the next one is integrated from the last one in order,
+        // not from the late one: 1 min, not 2.
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T10:03:00Z"), 1000.)),
+            &[("2026-05-31T22:00:00Z", 1000. / 60.)],
+        );
+    }
+
+    #[test]
+    fn test_split_at_local_midnight() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+
+        // 600 W for 10 minutes, from 23:55 to 00:05 in Zurich (CEST).
+        energy.production(utc("2026-06-01T21:55:00Z"), 600.);
+
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T22:05:00Z"), 600.)),
+            &[("2026-05-31T22:00:00Z", 50.), ("2026-06-01T22:00:00Z", 50.)],
+        );
+
+        // Days start at midnight in the time zone, not in UTC.
+        let mut energy = Energy::new(Tz::UTC);
+
+        energy.production(utc("2026-06-01T21:55:00Z"), 600.);
+
+        assert_energies(
+            &produced(energy.production(utc("2026-06-01T22:05:00Z"), 600.)),
+            &[("2026-06-01T00:00:00Z", 100.)],
+        );
+    }
+
+    #[test]
+    fn test_dst_days() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+
+        // Clocks go forward on 2026-03-29: the day starts at midnight
+        // CET, and lasts 23 hours, until midnight CEST.
+        energy.production(utc("2026-03-29T21:55:00Z"), 600.);
+
+        assert_energies(
+            &produced(energy.production(utc("2026-03-29T22:05:00Z"), 600.)),
+            &[("2026-03-28T23:00:00Z", 50.), ("2026-03-29T22:00:00Z", 50.)],
+        );
+
+        // Clocks go back on 2026-10-25: the day starts at midnight
+        // CEST, and lasts 25 hours, until midnight CET.
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+        energy.production(utc("2026-10-25T22:55:00Z"), 600.);
+
+        assert_energies(
+            &produced(energy.production(utc("2026-10-25T23:05:00Z"), 600.)),
+            &[("2026-10-24T22:00:00Z", 50.), ("2026-10-25T23:00:00Z", 50.)],
+        );
+
+        // During the repeated hour, the day doesn't change.
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+        energy.production(utc("2026-10-25T00:55:00Z"), 600.);
+
+        assert_energies(
+            &produced(energy.production(utc("2026-10-25T01:05:00Z"), 600.)),
+            &[("2026-10-24T22:00:00Z", 100.)],
+        );
+    }
+
+    #[test]
+    fn test_storage_is_split_into_charge_and_discharge() {
+        let mut energy = Energy::new(Tz::Europe__Zurich);
+        let storage = |energy: &mut Energy, time, power| {
+            counters(energy.storage(utc(time), power))
+                .into_iter()
+                .map(|counter| (counter.charged_energy, counter.discharged_energy))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(storage(&mut energy, "2026-06-01T10:00:00Z", 1200.).is_empty());
+
+        // Charging.
+        assert_eq!(
+            storage(&mut energy, "2026-06-01T10:01:00Z", 1200.),
+            [(20., 0.)]
+        );
+
+        // Discharging.
+        storage(&mut energy, "2026-06-01T10:02:00Z", -1200.);
+        assert_eq!(
+            storage(&mut energy, "2026-06-01T10:03:00Z", -1200.),
+            [(0., 20.)]
+        );
+
+        // From charging to discharging, the battery charges until the
+        // power crosses zero, after 30 s, then discharges.
+        storage(&mut energy, "2026-06-01T10:04:00Z", 1200.);
+        assert_eq!(
+            storage(&mut energy, "2026-06-01T10:05:00Z", -1200.),
+            [(5., 5.)]
+        );
+
+        // The crossing is not always in the middle: from -1200 W to
+        // 3600 W in 3 min, it's after 45 s.
+        assert_eq!(
+            storage(&mut energy, "2026-06-01T10:08:00Z", 3600.),
+            [(67.5, 7.5)]
+        );
+
+        // Both are split on the same days.
+        storage(&mut energy, "2026-06-01T21:59:00Z", 1200.);
+
+        let split = counters(energy.storage(utc("2026-06-01T22:01:00Z"), -1200.));
+
+        assert_eq!(
+            split.iter().map(|counter| counter.time).collect::<Vec<_>>(),
+            [utc("2026-05-31T22:00:00Z"), utc("2026-06-01T22:00:00Z")]
+        );
+        assert_eq!(
+            split
+                .iter()
+                .map(|counter| (counter.charged_energy, counter.discharged_energy))
+                .collect::<Vec<_>>(),
+            [(5., 5.), (5., 5.)]
+        );
+    }
+}
@@ -0,0 +1,698 @@
+use crate::{command::ExportFormat, storage::TABLES};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::DateTime;
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{Nullable, Text},
+};
+use parquet::arrow::ArrowWriter;
+use serde_json::Value;
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+    sync::Arc,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("table `{0}` is not a hypertable of the hub")]
+    UnknownTable(String),
+
+    #[error("failed to query the rows: {0}")]
+    Database(#[from] diesel::result::Error),
+
+    #[error("failed to read a row: {0}")]
+    Row(#[from] serde_json::Error),
+
+    #[error("failed to write the export: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("failed to write the CSV file: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("failed to build the Parquet columns: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    #[error("failed to write the Parquet file: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+}
+
+/// What a column holds, which defines how it is aggregated in a
+/// bucket, and its Parquet type.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// An instantaneous value, averaged.
+    Measure,
+    /// An ever-increasing value, the maximum is kept.
+    Counter,
+    /// A total over a period, the totals are summed.
+    Total,
+    Boolean,
+    /// A text, or a code, the most frequent value is kept.
+    Text,
+    Time,
+}
+
+struct Column {
+    name: &'static str,
+    kind: Kind,
+    unit: Option<&'static str>,
+}
+
+const fn measure(name: &'static str, unit: Option<&'static str>) -> Column {
+    Column {
+        name,
+        kind: Kind::Measure,
+        unit,
+    }
+}
+
+const fn counter(name: &'static str, unit: &'static str) -> Column {
+    Column {
+        name,
+        kind: Kind::Counter,
+        unit: Some(unit),
+    }
+}
+
+const fn total(name: &'static str, unit: &'static str) -> Column {
+    Column {
+        name,
+        kind: Kind::Total,
+        unit: Some(unit),
+    }
+}
+
+const fn boolean(name: &'static str) -> Column {
+    Column {
+        name,
+        kind: Kind::Boolean,
+        unit: None,
+    }
+}
+
+const fn text(name: &'static str) -> Column {
+    Column {
+        name,
+        kind: Kind::Text,
+        unit: None,
+    }
+}
+
+const fn time(name: &'static str) -> Column {
+    Column {
+        name,
+        kind: Kind::Time,
+        unit: Some("UTC"),
+    }
+}
+
+/// The exported columns of a hypertable.
+struct Table {
+    time: &'static str,
+    /// Columns that identify a series within the table: rows are
+    /// bucketed per series.
+    keys: &'static [Column],
+    columns: &'static [Column],
+}
+
+const ELECTRICITY_PRODUCTION: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        measure("power", Some("W")),
+        measure("voltage", Some("V")),
+        measure("current", Some("A")),
+        measure("frequency", Some("Hz")),
+        measure("l1_power", Some("W")),
+        measure("l1_voltage", Some("V")),
+        measure("l1_current", Some("A")),
+        measure("l1_frequency", Some("Hz")),
+        measure("l2_power", Some("W")),
+        measure("l2_voltage", Some("V")),
+        measure("l2_current", Some("A")),
+        measure("l2_frequency", Some("Hz")),
+        measure("l3_power", Some("W")),
+        measure("l3_voltage", Some("V")),
+        measure("l3_current", Some("A")),
+        measure("l3_frequency", Some("Hz")),
+    ],
+};
+
+const ELECTRICITY_STORAGE: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        measure("ongoing_power", Some("W")),
+        measure("state_of_charge", Some("%")),
+        measure("voltage", Some("V")),
+        measure("temperature", Some("°C")),
+        text("state"),
+        measure("health", Some("%")),
+    ],
+};
+
+const ELECTRICITY_CONSUMPTION: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        measure("house_power", Some("W")),
+        measure("house_l1_power", Some("W")),
+        measure("house_l2_power", Some("W")),
+        measure("house_l3_power", Some("W")),
+    ],
+};
+
+const DOMESTIC_HOT_WATER: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        measure("top_of_the_tank_temperature", Some("°C")),
+        measure("bottom_of_the_tank_temperature", Some("°C")),
+        measure("wanted_temperature", Some("°C")),
+    ],
+};
+
+const AIR: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        text("state"),
+        measure("inside_humidity", Some("%")),
+        measure("inside_co2_level", Some("ppm")),
+        measure(
+            "supplied_temperature_after_ground_coupled_heat_exchanger",
+            Some("°C"),
+        ),
+        measure(
+            "supplied_temperature_after_heat_recovery_exchanger",
+            Some("°C"),
+        ),
+        measure("extracted_temperature", Some("°C")),
+        measure("discharged_temperature", Some("°C")),
+        measure("wanted_temperature", Some("°C")),
+        measure("supplied_air_fan_speed", Some("rpm")),
+        measure("extracted_air_fan_speed", Some("rpm")),
+    ],
+};
+
+const EV_CHARGING: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        boolean("is_available"),
+        boolean("is_charging"),
+        text("number_of_phases"),
+        measure("power", Some("W")),
+        measure("applied_max_current", Some("A")),
+        measure("max_current", Some("A")),
+        counter("delivered_energy", "Wh"),
+        measure("station_temperature", Some("°C")),
+    ],
+};
+
+const VEHICLE: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        measure("state_of_charge", Some("%")),
+        boolean("is_charging"),
+        measure("remaining_range", Some("km")),
+        counter("odometer", "km"),
+    ],
+};
+
+const WEATHER_COLUMNS: &[Column] = &[
+    measure("temperature", Some("°C")),
+    measure("apparent_temperature", Some("°C")),
+    measure("dew_point", Some("°C")),
+    measure("humidity", Some("%")),
+    measure("pressure", Some("hPa")),
+    measure("clouds", Some("%")),
+    measure("uv_index", None),
+    measure("visibility", Some("m")),
+    measure("wind_degree", Some("°")),
+    measure("wind_speed", Some("m/s")),
+    measure("wind_gust", Some("m/s")),
+    measure("rain", Some("mm")),
+    measure("snow", Some("mm")),
+    text("condition"),
+];
+
+const WEATHER: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: WEATHER_COLUMNS,
+};
+
+const WEATHER_FORECAST: Table = Table {
+    time: "fetched_at",
+    keys: &[time("forecast_for")],
+    columns: WEATHER_COLUMNS,
+};
+
+const THING_PROPERTY: Table = Table {
+    time: "time",
+    keys: &[text("thing_id"), text("property_name"), text("unit")],
+    columns: &[measure("numeric_value", None), text("text_value")],
+};
+
+const ENERGY_COUNTER: Table = Table {
+    time: "time",
+    keys: &[],
+    columns: &[
+        total("produced_energy", "Wh"),
+        total("consumed_energy", "Wh"),
+        total("charged_energy", "Wh"),
+        total("discharged_energy", "Wh"),
+    ],
+};
+
+const REJECTED_SAMPLES: Table = Table {
+    time: "time",
+    keys: &[text("thing_id"), text("property_name")],
+    columns: &[text("value"), text("reason")],
+};
+
+fn table(name: &str) -> Result<&'static Table, Error> {
+    Ok(match name {
+        "electricity_production" => &ELECTRICITY_PRODUCTION,
+        "electricity_storage" => &ELECTRICITY_STORAGE,
+        "electricity_consumption" => &ELECTRICITY_CONSUMPTION,
+        "domestic_hot_water" => &DOMESTIC_HOT_WATER,
+        "air" => &AIR,
+        "ev_charging" => &EV_CHARGING,
+        "vehicle" => &VEHICLE,
+        "weather" => &WEATHER,
+        "weather_forecast" => &WEATHER_FORECAST,
+        "thing_property" => &THING_PROPERTY,
+        "energy_counter" => &ENERGY_COUNTER,
+        "rejected_samples" => &REJECTED_SAMPLES,
+        _ => return Err(Error::UnknownTable(name.to_string())),
+    })
+}
+
+/// A row, as a JSON array, in the order of the header.
+#[derive(QueryableByName)]
+struct ExportedRow {
+    #[sql_type = "Text"]
+    row: String,
+}
+
+/// Export the rows of the given tables, or of all the tables if none
+/// is given, between `from` (included) and `to` (excluded, now by
+/// default), one file per table in `output`. If `bucket` is set, rows
+/// are aggregated per bucket of this size, aligned on `time_zone` so
+/// that a `1 day` bucket is a local day, like the daily aggregates.
+#[allow(clippy::too_many_arguments)]
+pub fn export(
+    connection: &PgConnection,
+    tables: &[String],
+    from: &str,
+    to: Option<&str>,
+    bucket: Option<&str>,
+    time_zone: &str,
+    format: ExportFormat,
+    output: &Path,
+) -> Result<(), Error> {
+    let tables = if tables.is_empty() {
+        TABLES.iter().map(|table| table.to_string()).collect()
+    } else {
+        tables.to_vec()
+    };
+
+    fs::create_dir_all(output)?;
+
+    // Times are exported in UTC, whatever the time zone of the server.
+    sql_query("SET TIME ZONE 'UTC'").execute(connection)?;
+
+    for table_name in &tables {
+        let table = table(table_name)?;
+
+        let query = sql_query(query(table_name, table, bucket.is_some()))
+            .bind::<Text, _>(from)
+            .bind::<Nullable<Text>, _>(to);
+
+        let rows = match bucket {
+            Some(bucket) => query
+                .bind::<Text, _>(bucket)
+                .bind::<Text, _>(time_zone)
+                .load::<ExportedRow>(connection)?,
+            None => query.load::<ExportedRow>(connection)?,
+        };
+
+        let rows = rows
+            .into_iter()
+            .map(|row| serde_json::from_str::<Vec<Value>>(&row.row))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let columns = [time(table.time)]
+            .iter()
+            .chain(table.keys)
+            .chain(table.columns)
+            .map(|column| (header(column), column.kind))
+            .collect::<Vec<_>>();
+
+        let path = output.join(format!(
+            "{}.{}",
+            table_name,
+            match format {
+                ExportFormat::Csv => "csv",
+                ExportFormat::Parquet => "parquet",
+            }
+        ));
+
+        match format {
+            ExportFormat::Csv => write_csv(&path, &columns, &rows)?,
+            ExportFormat::Parquet => write_parquet(&path, &columns, &rows)?,
+        }
+
+        println!(
+            "Exported {} row(s) of `{}` to `{}`",
+            rows.len(),
+            table_name,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// The header of a column, with its unit, e.g. `power (W)`.
+fn header(column: &Column) -> String {
+    match column.unit {
+        Some(unit) => format!("{} ({})", column.name, unit),
+        None => column.name.to_string(),
+    }
+}
+
+/// Build the query selecting each row as a JSON array. `$1` and `$2`
+/// are the time range, `$3` the bucket size and `$4` its time zone if
+/// `bucketed`.
+fn query(table_name: &str, table: &Table, bucketed: bool) -> String {
+    let keys = table
+        .keys
+        .iter()
+        .map(|column| column.name)
+        .collect::<Vec<_>>();
+
+    let columns = table
+        .columns
+        .iter()
+        .map(|column| match (bucketed, column.kind) {
+            (false, Kind::Text) => format!("{}::text", column.name),
+            (false, _) => column.name.to_string(),
+            (true, Kind::Measure) => format!("avg({})", column.name),
+            (true, Kind::Counter | Kind::Time) => format!("max({})", column.name),
+            (true, Kind::Total) => format!("sum({})", column.name),
+            (true, Kind::Boolean) => format!("bool_or({})", column.name),
+            (true, Kind::Text) => format!("mode() WITHIN GROUP (ORDER BY {}::text)", column.name),
+        })
+        .collect::<Vec<_>>();
+
+    let time = if bucketed { "bucket" } else { table.time };
+    let selected = [time]
+        .into_iter()
+        .chain(keys.iter().copied())
+        .chain(columns.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let order = [time]
+        .into_iter()
+        .chain(keys.iter().copied())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let range = format!(
+        "{time} >= $1::timestamptz AND {time} < coalesce($2::timestamptz, now())",
+        time = table.time
+    );
+
+    if bucketed {
+        format!(
+            "SELECT json_build_array({selected})::text AS row \
+            FROM (SELECT time_bucket($3::interval, {time}, $4) AS bucket, * FROM {table} WHERE {range}) AS rows \
+            GROUP BY {order} \
+            ORDER BY {order}",
+            selected = selected,
+            time = table.time,
+            table = table_name,
+            range = range,
+            order = order,
+        )
+    } else {
+        format!(
+            "SELECT json_build_array({selected})::text AS row \
+            FROM {table} \
+            WHERE {range} \
+            ORDER BY {order}",
+            selected = selected,
+            table = table_name,
+            range = range,
+            order = order,
+        )
+    }
+}
+
+fn write_csv(path: &Path, columns: &[(String, Kind)], rows: &[Vec<Value>]) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record(columns.iter().map(|(header, _)| header))?;
+
+    for row in rows {
+        writer.write_record(row.iter().map(|value| match value {
+            Value::Null => String::new(),
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }))?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_parquet(
+    path: &Path,
+    columns: &[(String, Kind)],
+    rows: &[Vec<Value>],
+) -> Result<(), Error> {
+    let fields = columns
+        .iter()
+        .map(|(header, kind)| {
+            let data_type = match kind {
+                Kind::Measure | Kind::Counter | Kind::Total => DataType::Float64,
+                Kind::Boolean => DataType::Boolean,
+                Kind::Text => DataType::Utf8,
+                Kind::Time => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            };
+
+            Field::new(header, data_type, true)
+        })
+        .collect::<Vec<_>>();
+
+    let arrays = columns
+        .iter()
+        .enumerate()
+        .map(|(nth, (_, kind))| {
+            let values = rows.iter().map(|row| row.get(nth).unwrap_or(&Value::Null));
+
+            match kind {
+                Kind::Measure | Kind::Counter | Kind::Total => {
+                    Arc::new(Float64Array::from_iter(values.map(|value| value.as_f64())))
+                        as ArrayRef
+                }
+                Kind::Boolean => {
+                    Arc::new(BooleanArray::from_iter(values.map(|value| value.as_bool())))
+                }
+                Kind::Text => Arc::new(StringArray::from_iter(values.map(|value| match value {
+                    Value::Null => None,
+                    Value::String(value) => Some(value.clone()),
+                    value => Some(value.to_string()),
+                }))),
+                Kind::Time => Arc::new(
+                    TimestampMicrosecondArray::from_iter(values.map(|value| {
+                        DateTime::parse_from_rfc3339(value.as_str()?)
+                            .ok()
+                            .map(|time| time.timestamp_micros())
+                    }))
+                    .with_timezone("UTC"),
+                ),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?;
+
+    let mut writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "hub-event-aggregator-export-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn columns() -> Vec<(String, Kind)> {
+        vec![
+            ("time (UTC)".to_string(), Kind::Time),
+            ("power (W)".to_string(), Kind::Measure),
+            ("text_value".to_string(), Kind::Text),
+            ("forecast_for (UTC)".to_string(), Kind::Time),
+        ]
+    }
+
+    fn rows() -> Vec<Vec<Value>> {
+        vec![
+            vec![
+                json!("2026-06-01T10:00:00+00:00"),
+                json!(1.5),
+                json!("on"),
+                json!("2026-06-01T12:00:00.5+00:00"),
+            ],
+            vec![
+                json!("2026-06-01T10:01:00+00:00"),
+                Value::Null,
+                Value::Null,
+                Value::Null,
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(
+            query("thing_property", &THING_PROPERTY, false),
+            "SELECT json_build_array(time, thing_id, property_name, unit, numeric_value, text_value::text)::text AS row \
+            FROM thing_property \
+            WHERE time >= $1::timestamptz AND time < coalesce($2::timestamptz, now()) \
+            ORDER BY time, thing_id, property_name, unit"
+        );
+
+        let query = query("weather_forecast", &WEATHER_FORECAST, false);
+
+        assert!(query.starts_with("SELECT json_build_array(fetched_at, forecast_for, "));
+        assert!(query.contains(
+            "WHERE fetched_at >= $1::timestamptz AND fetched_at < coalesce($2::timestamptz, now())"
+        ));
+        assert!(query.ends_with("ORDER BY fetched_at, forecast_for"));
+    }
+
+    #[test]
+    fn test_bucketed_query() {
+        assert_eq!(
+            query("thing_property", &THING_PROPERTY, true),
+            "SELECT json_build_array(bucket, thing_id, property_name, unit, avg(numeric_value), mode() WITHIN GROUP (ORDER BY text_value::text))::text AS row \
+            FROM (SELECT time_bucket($3::interval, time, $4) AS bucket, * FROM thing_property \
+            WHERE time >= $1::timestamptz AND time < coalesce($2::timestamptz, now())) AS rows \
+            GROUP BY bucket, thing_id, property_name, unit \
+            ORDER BY bucket, thing_id, property_name, unit"
+        );
+
+        let query = query("weather_forecast", &WEATHER_FORECAST, true);
+
+        assert!(query.starts_with("SELECT json_build_array(bucket, forecast_for, "));
+        assert!(query.contains(
+            "FROM (SELECT time_bucket($3::interval, fetched_at, $4) AS bucket, * FROM weather_forecast \
+            WHERE fetched_at >= $1::timestamptz AND fetched_at < coalesce($2::timestamptz, now())) AS rows"
+        ));
+        assert!(query.ends_with("GROUP BY bucket, forecast_for ORDER BY bucket, forecast_for"));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let path = directory("csv").join("thing_property.csv");
+
+        write_csv(&path, &columns(), &rows()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "time (UTC),power (W),text_value,forecast_for (UTC)\n\
+            2026-06-01T10:00:00+00:00,1.5,on,2026-06-01T12:00:00.5+00:00\n\
+            2026-06-01T10:01:00+00:00,,,\n"
+        );
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let path = directory("parquet").join("thing_property.parquet");
+
+        write_parquet(&path, &columns(), &rows()).unwrap();
+
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        );
+
+        let times = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+
+        assert_eq!(times.value(0), 1_780_308_000_000_000);
+        assert_eq!(times.value(1), 1_780_308_060_000_000);
+
+        let powers = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+
+        assert_eq!(powers.value(0), 1.5);
+        assert!(powers.is_null(1));
+
+        let texts = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+
+        assert_eq!(texts.value(0), "on");
+        assert!(texts.is_null(1));
+
+        let forecasts = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+
+        assert_eq!(forecasts.value(0), 1_780_315_200_500_000);
+        assert!(forecasts.is_null(1));
+    }
+}
@@ -0,0 +1,821 @@
+use crate::{
+    energy::Energy,
+    storage::{self, Row},
+};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use csv::{ReaderBuilder, StringRecord};
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{BigInt, Bool, Text, Timestamptz},
+};
+use hub_database::{continuous_aggregates, models};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::Path,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("`{0}` is not an imported column, it must be one of: {}", COLUMNS.map(|column| column.name).join(", "))]
+    UnknownColumn(String),
+
+    #[error("`{0}` has no row with a timestamp in its first column")]
+    NoRows(String),
+
+    #[error("failed to read the file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("failed to read the CSV file: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("failed to insert the rows: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// A column of a hypertable that can be imported.
+struct Column {
+    /// `<table>.<column>`.
+    name: &'static str,
+    /// Words of the header of the column in the VRM exports.
+    vrm_header: Option<&'static str>,
+    /// Whether the value can be computed from the other columns when
+    /// the CSV file doesn't have it.
+    derived: bool,
+}
+
+const fn column(name: &'static str, vrm_header: &'static str) -> Column {
+    Column {
+        name,
+        vrm_header: Some(vrm_header),
+        derived: false,
+    }
+}
+
+const fn derived(name: &'static str) -> Column {
+    Column {
+        name,
+        vrm_header: None,
+        derived: true,
+    }
+}
+
+/// The imported columns. PV inverters are AC-coupled on the output of
+/// the inverter/charger (the VE.Bus system), so their voltage and
+/// frequency are the ones of the output.
+const COLUMNS: [Column; 24] = [
+    column(
+        "electricity_production.l1_voltage",
+        "output voltage phase 1",
+    ),
+    column("electricity_production.l1_frequency", "output frequency"),
+    column(
+        "electricity_production.l1_power",
+        "pv ac coupled on output l1",
+    ),
+    derived("electricity_production.l1_current"),
+    column(
+        "electricity_production.l2_voltage",
+        "output voltage phase 2",
+    ),
+    column("electricity_production.l2_frequency", "output frequency"),
+    column(
+        "electricity_production.l2_power",
+        "pv ac coupled on output l2",
+    ),
+    derived("electricity_production.l2_current"),
+    column(
+        "electricity_production.l3_voltage",
+        "output voltage phase 3",
+    ),
+    column("electricity_production.l3_frequency", "output frequency"),
+    column(
+        "electricity_production.l3_power",
+        "pv ac coupled on output l3",
+    ),
+    derived("electricity_production.l3_current"),
+    derived("electricity_production.voltage"),
+    derived("electricity_production.frequency"),
+    derived("electricity_production.power"),
+    derived("electricity_production.current"),
+    column("electricity_storage.ongoing_power", "battery power"),
+    column("electricity_storage.temperature", "battery temperature"),
+    column("electricity_storage.state_of_charge", "state of charge|soc"),
+    column("electricity_storage.voltage", "battery voltage"),
+    derived("electricity_consumption.house_power"),
+    column(
+        "electricity_consumption.house_l1_power",
+        "ac consumption l1",
+    ),
+    column(
+        "electricity_consumption.house_l2_power",
+        "ac consumption l2",
+    ),
+    column(
+        "electricity_consumption.house_l3_power",
+        "ac consumption l3",
+    ),
+];
+
+const TABLES: [&str; 3] = [
+    "electricity_production",
+    "electricity_storage",
+    "electricity_consumption",
+];
+
+/// Import CSV files exported by the Victron VRM portal into the
+/// `electricity_production`, `electricity_storage` and
+/// `electricity_consumption` tables, and integrate them into the energy
+/// counters.
+///
+/// The first column holds the timestamps, either Unix timestamps or
+/// dates in `time_zone`. The rows before the first timestamp are the
+/// headers. The other columns are found by the words of their headers:
+/// `headers` adds to, or replaces, the headers of the VRM exports.
+///
+/// Rows are de-duplicated: a row is skipped if its table already has
+/// rows in the same interval, the interval being the time between two
+/// rows of the file. Importing a file twice, or a file that overlaps
+/// with the aggregated rows, is harmless.
+pub fn import(
+    connection: &PgConnection,
+    files: &[impl AsRef<Path>],
+    time_zone: Tz,
+    headers: &BTreeMap<String, String>,
+    drop_after: &BTreeMap<String, String>,
+) -> Result<(), Error> {
+    if let Some(name) = headers
+        .keys()
+        .find(|name| !COLUMNS.iter().any(|column| column.name == name.as_str()))
+    {
+        return Err(Error::UnknownColumn(name.clone()));
+    }
+
+    let headers = COLUMNS
+        .iter()
+        .filter_map(|column| {
+            Some((
+                column.name,
+                headers
+                    .get(column.name)
+                    .map(String::as_str)
+                    .or(column.vrm_header)?,
+            ))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for file in files {
+        import_file(connection, file.as_ref(), time_zone, &headers, drop_after)?;
+    }
+
+    // Imported rows are usually older than what the refresh policies
+    // cover.
+    if let Err(error) = continuous_aggregates::refresh(connection, drop_after) {
+        eprintln!(
+            "Failed to refresh the continuous aggregates, the aggregator will refresh them when it starts: {}",
+            error
+        );
+    }
+
+    Ok(())
+}
+
+fn import_file(
+    connection: &PgConnection,
+    path: &Path,
+    time_zone: Tz,
+    headers: &BTreeMap<&'static str, &str>,
+    drop_after: &BTreeMap<String, String>,
+) -> Result<(), Error> {
+    let export = Export::parse(&fs::read_to_string(path)?, time_zone, headers)?;
+
+    let (from, to) = export
+        .period()
+        .ok_or_else(|| Error::NoRows(path.display().to_string()))?;
+
+    for table in TABLES {
+        let missing = export.columns.missing(table);
+
+        if !missing.is_empty() {
+            eprintln!(
+                "Not importing `{}` from `{}`, columns are missing: {}",
+                table,
+                path.display(),
+                missing.join(", ")
+            );
+        }
+    }
+
+    let [production_buckets, storage_buckets, consumption_buckets] =
+        TABLES.map(|table| buckets(connection, table, export.step, from, to));
+    let existing_buckets = [production_buckets?, storage_buckets?, consumption_buckets?];
+
+    let (rows, number_of_rows, number_of_skipped_rows) = export.rows(time_zone, &existing_buckets);
+
+    storage::insert_all(connection, &rows)?;
+
+    println!(
+        "Imported {} row(s) from `{}`, skipped {} row(s) already in the database",
+        number_of_rows,
+        path.display(),
+        number_of_skipped_rows
+    );
+
+    for table in TABLES {
+        if !export.columns.missing(table).is_empty() {
+            continue;
+        }
+
+        if let Some(drop_after) = drop_after.get(table) {
+            if is_older(connection, from, drop_after)? {
+                eprintln!(
+                    "Rows of `{}` older than `{}` will be dropped by its retention policy, remove it with `policies remove --retention {}` to keep them",
+                    table, drop_after, table
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The content of a CSV file.
+struct Export {
+    /// The rows with a timestamp, sorted by time.
+    records: Vec<(DateTime<Utc>, StringRecord)>,
+    columns: Columns,
+    /// The interval between two rows, in seconds.
+    step: i64,
+}
+
+impl Export {
+    fn parse(
+        content: &str,
+        time_zone: Tz,
+        headers: &BTreeMap<&'static str, &str>,
+    ) -> Result<Self, csv::Error> {
+        // Depending on the locale, VRM separates the values with `;`,
+        // and the decimals with `,`.
+        let first_line = content.lines().next().unwrap_or_default();
+        let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() {
+            b';'
+        } else {
+            b','
+        };
+
+        let mut header_rows = Vec::new();
+        let mut records = Vec::new();
+
+        for record in ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(content.as_bytes())
+            .records()
+        {
+            let record = record?;
+
+            match record.get(0).and_then(|value| parse_time(value, time_zone)) {
+                Some(time) => records.push((time, record)),
+                None if records.is_empty() => header_rows.push(record),
+                None => (),
+            }
+        }
+
+        records.sort_by_key(|(time, _)| *time);
+
+        let step = records
+            .windows(2)
+            .map(|pair| (pair[1].0 - pair[0].0).num_seconds())
+            .filter(|seconds| *seconds > 0)
+            .min()
+            .unwrap_or(60);
+
+        Ok(Self {
+            records,
+            columns: Columns::new(&header_rows, headers, delimiter == b';'),
+            step,
+        })
+    }
+
+    /// The times of the first and the last rows.
+    fn period(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        Some((self.records.first()?.0, self.records.last()?.0))
+    }
+
+    /// The rows to insert, with the energy counters, and the numbers of
+    /// imported and skipped rows. A row is skipped if its bucket (its
+    /// interval of `step` seconds, numbered from the Unix epoch) is in
+    /// the existing buckets of its table, in the order of `TABLES`.
+    fn rows(
+        &self,
+        time_zone: Tz,
+        existing_buckets: &[BTreeSet<i64>; 3],
+    ) -> (Vec<Row>, usize, usize) {
+        let [production_buckets, storage_buckets, consumption_buckets] = existing_buckets;
+
+        let mut energy = Energy::new(time_zone);
+        let mut rows = Vec::new();
+        let mut number_of_rows = 0;
+        let mut number_of_skipped_rows = 0;
+
+        for (time, record) in &self.records {
+            let time = *time;
+            let bucket = time.timestamp().div_euclid(self.step);
+
+            if let Some(row) = self.columns.production(time, record) {
+                if production_buckets.contains(&bucket) {
+                    number_of_skipped_rows += 1;
+                } else {
+                    rows.extend(energy.production(time, row.power));
+                    rows.push(Row::ElectricityProduction(row));
+                    number_of_rows += 1;
+                }
+            }
+
+            if let Some(row) = self.columns.storage(time, record) {
+                if storage_buckets.contains(&bucket) {
+                    number_of_skipped_rows += 1;
+                } else {
+                    rows.extend(energy.storage(time, row.ongoing_power));
+                    rows.push(Row::ElectricityStorage(row));
+                    number_of_rows += 1;
+                }
+            }
+
+            if let Some(row) = self.columns.consumption(time, record) {
+                if consumption_buckets.contains(&bucket) {
+                    number_of_skipped_rows += 1;
+                } else {
+                    rows.extend(energy.consumption(time, row.house_power));
+                    rows.push(Row::ElectricityConsumption(row));
+                    number_of_rows += 1;
+                }
+            }
+        }
+
+        (rows, number_of_rows, number_of_skipped_rows)
+    }
+}
+
+/// Where the imported columns are in a CSV file.
+struct Columns {
+    indexes: BTreeMap<&'static str, usize>,
+    decimal_comma: bool,
+}
+
+impl Columns {
+    /// Find the columns by their headers. A header matches if it has
+    /// all the words of one of the `|`-separated alternatives, the
+    /// first matching column is used. Headers spanning several rows
+    /// (e.g. the device, the name, and the unit) are joined.
+    fn new(
+        header_rows: &[StringRecord],
+        headers: &BTreeMap<&'static str, &str>,
+        decimal_comma: bool,
+    ) -> Self {
+        let number_of_columns = header_rows.iter().map(StringRecord::len).max().unwrap_or(0);
+        let header_words = (0..number_of_columns)
+            .map(|index| {
+                words(
+                    &header_rows
+                        .iter()
+                        .filter_map(|header_row| header_row.get(index))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let indexes = headers
+            .iter()
+            .filter_map(|(name, header)| {
+                let index = header.split('|').find_map(|alternative| {
+                    let alternative = words(alternative);
+
+                    header_words
+                        .iter()
+                        .skip(1)
+                        .position(|header_words| {
+                            alternative.iter().all(|word| header_words.contains(word))
+                        })
+                        .map(|position| position + 1)
+                })?;
+
+                Some((*name, index))
+            })
+            .collect();
+
+        Self {
+            indexes,
+            decimal_comma,
+        }
+    }
+
+    /// The columns of `table` that are needed but not found.
+    fn missing(&self, table: &str) -> Vec<&'static str> {
+        COLUMNS
+            .iter()
+            .filter(|column| {
+                !column.derived
+                    && column.name.split_once('.').map(|(table, _)| table) == Some(table)
+                    && !self.indexes.contains_key(column.name)
+            })
+            .map(|column| column.name)
+            .collect()
+    }
+
+    fn value(&self, record: &StringRecord, name: &str) -> Option<f64> {
+        let value = record.get(*self.indexes.get(name)?)?.trim();
+
+        let value = if self.decimal_comma {
+            value.replace(',', ".").parse::<f64>()
+        } else {
+            value.parse::<f64>()
+        };
+
+        value.ok().filter(|value| value.is_finite())
+    }
+
+    fn production(
+        &self,
+        time: DateTime<Utc>,
+        record: &StringRecord,
+    ) -> Option<models::ElectricityProduction> {
+        let phase = |phase: u8| {
+            let value = |name| {
+                self.value(
+                    record,
+                    &format!("electricity_production.l{}_{}", phase, name),
+                )
+            };
+
+            let voltage = value("voltage")?;
+            let power = value("power")?;
+
+            Some((
+                voltage,
+                value("frequency")?,
+                power,
+                value("current").unwrap_or(if voltage > 0. { power / voltage } else { 0. }),
+            ))
+        };
+
+        let (l1_voltage, l1_frequency, l1_power, l1_current) = phase(1)?;
+        let (l2_voltage, l2_frequency, l2_power, l2_current) = phase(2)?;
+        let (l3_voltage, l3_frequency, l3_power, l3_current) = phase(3)?;
+
+        Some(models::ElectricityProduction {
+            time,
+
+            l1_voltage,
+            l1_frequency,
+            l1_power,
+            l1_current,
+
+            l2_voltage,
+            l2_frequency,
+            l2_power,
+            l2_current,
+
+            l3_voltage,
+            l3_frequency,
+            l3_power,
+            l3_current,
+
+            voltage: self
+                .value(record, "electricity_production.voltage")
+                .unwrap_or((l1_voltage + l2_voltage + l3_voltage) / 3.),
+            frequency: self
+                .value(record, "electricity_production.frequency")
+                .unwrap_or((l1_frequency + l2_frequency + l3_frequency) / 3.),
+            power: self
+                .value(record, "electricity_production.power")
+                .unwrap_or(l1_power + l2_power + l3_power),
+            current: self
+                .value(record, "electricity_production.current")
+                .unwrap_or(l1_current + l2_current + l3_current),
+        })
+    }
+
+    fn storage(
+        &self,
+        time: DateTime<Utc>,
+        record: &StringRecord,
+    ) -> Option<models::ElectricityStorage> {
+        Some(models::ElectricityStorage {
+            time,
+            ongoing_power: self.value(record, "electricity_storage.ongoing_power")?,
+            temperature: self.value(record, "electricity_storage.temperature")?,
+            state_of_charge: self.value(record, "electricity_storage.state_of_charge")?,
+            voltage: self.value(record, "electricity_storage.voltage")?,
+            state: None,
+            health: None,
+        })
+    }
+
+    fn consumption(
+        &self,
+        time: DateTime<Utc>,
+        record: &StringRecord,
+    ) -> Option<models::ElectricityConsumption> {
+        let house_l1_power = self.value(record, "electricity_consumption.house_l1_power")?;
+        let house_l2_power = self.value(record, "electricity_consumption.house_l2_power")?;
+        let house_l3_power = self.value(record, "electricity_consumption.house_l3_power")?;
+
+        Some(models::ElectricityConsumption {
+            time,
+            house_power: self
+                .value(record, "electricity_consumption.house_power")
+                .unwrap_or(house_l1_power + house_l2_power + house_l3_power),
+            house_l1_power,
+            house_l2_power,
+            house_l3_power,
+        })
+    }
+}
+
+/// The lowercase alphanumeric words of a header.
+fn words(header: &str) -> Vec<String> {
+    header
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Parse a Unix timestamp, in seconds or milliseconds, an RFC 3339
+/// date, or a date without time zone, in `time_zone`.
+fn parse_time(value: &str, time_zone: Tz) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return if timestamp > 100_000_000_000 {
+            Utc.timestamp_millis_opt(timestamp).single()
+        } else {
+            Utc.timestamp_opt(timestamp, 0).single()
+        };
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .into_iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .and_then(|time| time_zone.from_local_datetime(&time).earliest())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[derive(QueryableByName)]
+struct Bucket {
+    #[sql_type = "BigInt"]
+    bucket: i64,
+}
+
+/// The intervals of `step` seconds, numbered from the Unix epoch, that
+/// have rows in `table` between `from` and `to`.
+fn buckets(
+    connection: &PgConnection,
+    table: &str,
+    step: i64,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> QueryResult<BTreeSet<i64>> {
+    Ok(sql_query(format!(
+        "SELECT DISTINCT floor(extract(epoch FROM time) / $1)::bigint AS bucket FROM {} WHERE time >= $2 AND time < $3",
+        table
+    ))
+    .bind::<BigInt, _>(step)
+    .bind::<Timestamptz, _>(Utc.timestamp_opt(from.timestamp().div_euclid(step) * step, 0).unwrap())
+    .bind::<Timestamptz, _>(to + Duration::seconds(step))
+    .load::<Bucket>(connection)?
+    .into_iter()
+    .map(|bucket| bucket.bucket)
+    .collect())
+}
+
+#[derive(QueryableByName)]
+struct Older {
+    #[sql_type = "Bool"]
+    older: bool,
+}
+
+/// Whether `time` is older than the `interval` before now.
+fn is_older(connection: &PgConnection, time: DateTime<Utc>, interval: &str) -> QueryResult<bool> {
+    Ok(sql_query("SELECT $1 < now() - $2::interval AS older")
+        .bind::<Timestamptz, _>(time)
+        .bind::<Text, _>(interval)
+        .load::<Older>(connection)?
+        .first()
+        .is_some_and(|older| older.older))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vrm_headers() -> BTreeMap<&'static str, &'static str> {
+        COLUMNS
+            .iter()
+            .filter_map(|column| Some((column.name, column.vrm_header?)))
+            .collect()
+    }
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    /// The buckets of the rows of each table, as the database would
+    /// return them once the rows are inserted.
+    fn buckets_of(rows: &[Row], step: i64) -> [BTreeSet<i64>; 3] {
+        TABLES.map(|table| {
+            rows.iter()
+                .filter(|row| row.table_name() == table)
+                .map(|row| row.time().timestamp().div_euclid(step))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_parse_vrm_export() {
+        let export = Export::parse(
+            include_str!("import_test.csv"),
+            Tz::Europe__Zurich,
+            &vrm_headers(),
+        )
+        .unwrap();
+
+        // Times are local, and the rows are sorted.
+        assert_eq!(
+            export.period(),
+            Some((utc("2026-06-01T08:00:00Z"), utc("2026-06-01T08:45:00Z")))
+        );
+        assert_eq!(export.step, 15 * 60);
+
+        for table in TABLES {
+            assert!(export.columns.missing(table).is_empty(), "{}", table);
+        }
+
+        let (rows, number_of_rows, number_of_skipped_rows) =
+            export.rows(Tz::Europe__Zurich, &Default::default());
+
+        // Rows with empty cells are not imported: the battery values are
+        // missing at 10:30, and the consumption at 10:45.
+        assert_eq!(number_of_rows, 10);
+        assert_eq!(number_of_skipped_rows, 0);
+
+        let production = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::ElectricityProduction(row) => Some(row),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let storage = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::ElectricityStorage(row) => Some(row),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let consumption = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::ElectricityConsumption(row) => Some(row),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(production.len(), 4);
+        assert_eq!(storage.len(), 3);
+        assert_eq!(consumption.len(), 3);
+
+        let production = production[0];
+
+        assert_eq!(production.time, utc("2026-06-01T08:00:00Z"));
+        assert_eq!(production.l1_voltage, 230.1);
+        assert_eq!(production.l2_voltage, 231.2);
+        assert_eq!(production.l3_voltage, 229.8);
+        assert_eq!(production.l1_frequency, 50.01);
+        assert_eq!(production.l3_frequency, 50.01);
+        assert_eq!(production.l1_power, 900.);
+        assert_eq!(production.l2_power, 1000.);
+        assert_eq!(production.l3_power, 1100.);
+
+        // Derived columns.
+        assert_eq!(production.l1_current, 900. / 230.1);
+        assert_eq!(production.power, 3000.);
+        assert!((production.voltage - 230.366_666).abs() < 1e-5);
+        assert_eq!(production.frequency, 50.01);
+
+        assert_eq!(storage[1].time, utc("2026-06-01T08:15:00Z"));
+        assert_eq!(storage[1].ongoing_power, -250.);
+        assert_eq!(storage[1].voltage, 52.1);
+        assert_eq!(storage[1].state_of_charge, 80.5);
+        assert_eq!(storage[1].temperature, 21.3);
+        assert_eq!(storage[2].time, utc("2026-06-01T08:45:00Z"));
+
+        assert_eq!(consumption[0].house_l1_power, 350.);
+        assert_eq!(consumption[0].house_power, 1350.);
+
+        // The powers are integrated into the energy counters.
+        let produced_energy = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::EnergyCounter(row) => Some(row.produced_energy),
+                _ => None,
+            })
+            .sum::<f64>();
+
+        // Trapezoids of 15 minutes between 3000, 3300, 3900 and 4200 W.
+        assert!((produced_energy - (3150. + 3600. + 4050.) / 4.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_import_twice() {
+        let export = Export::parse(
+            include_str!("import_test.csv"),
+            Tz::Europe__Zurich,
+            &vrm_headers(),
+        )
+        .unwrap();
+
+        let (rows, _, _) = export.rows(Tz::Europe__Zurich, &Default::default());
+        let existing_buckets = buckets_of(&rows, export.step);
+
+        // Everything is already in the database, even the energy.
+        let (rows, number_of_rows, number_of_skipped_rows) =
+            export.rows(Tz::Europe__Zurich, &existing_buckets);
+
+        assert!(rows.is_empty());
+        assert_eq!(number_of_rows, 0);
+        assert_eq!(number_of_skipped_rows, 10);
+
+        // Only the missing rows are imported.
+        let mut existing_buckets = existing_buckets;
+        existing_buckets[1].clear();
+
+        let (rows, number_of_rows, number_of_skipped_rows) =
+            export.rows(Tz::Europe__Zurich, &existing_buckets);
+
+        assert_eq!(number_of_rows, 3);
+        assert_eq!(number_of_skipped_rows, 7);
+        assert!(rows
+            .iter()
+            .all(|row| matches!(row, Row::ElectricityStorage(_) | Row::EnergyCounter(_))));
+    }
+
+    #[test]
+    fn test_parse_localized_export() {
+        let content = "timestamp;System overview;System overview;System overview\n\
+                       ;AC Consumption L1;AC Consumption L2;AC Consumption L3\n\
+                       ;W;W;W\n\
+                       1780300800;1,5;2,5;3,5\n\
+                       1780300860;4;5;6\n";
+
+        let export = Export::parse(content, Tz::Europe__Zurich, &vrm_headers()).unwrap();
+
+        assert_eq!(export.step, 60);
+        assert_eq!(
+            export.columns.missing("electricity_storage"),
+            [
+                "electricity_storage.ongoing_power",
+                "electricity_storage.temperature",
+                "electricity_storage.state_of_charge",
+                "electricity_storage.voltage",
+            ]
+        );
+
+        let (rows, number_of_rows, _) = export.rows(Tz::Europe__Zurich, &Default::default());
+
+        assert_eq!(number_of_rows, 2);
+
+        match &rows[0] {
+            Row::ElectricityConsumption(row) => {
+                assert_eq!(row.time, utc("2026-06-01T08:00:00Z"));
+                assert_eq!(row.house_l1_power, 1.5);
+                assert_eq!(row.house_power, 7.5);
+            }
+            row => panic!("unexpected row {:?}", row),
+        }
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let zurich = Tz::Europe__Zurich;
+        let expected = Some(utc("2026-06-01T08:00:00Z"));
+
+        assert_eq!(parse_time("1780300800", zurich), expected);
+        assert_eq!(parse_time("1780300800000", zurich), expected);
+        assert_eq!(parse_time("2026-06-01T10:00:00+02:00", zurich), expected);
+        assert_eq!(parse_time(" 2026-06-01 10:00:00 ", zurich), expected);
+        assert_eq!(parse_time("2026-06-01 10:00", zurich), expected);
+        assert_eq!(parse_time("", zurich), None);
+        assert_eq!(parse_time("timestamp", zurich), None);
+    }
+}
@@ -1,15 +1,36 @@
 mod aggregator;
 mod command;
 mod configuration;
-mod database;
+mod discovery;
+mod energy;
+mod export;
+mod import;
+mod metrics;
+mod policies;
+mod push;
+mod sink;
+mod storage;
+mod subscription;
 mod thing;
+mod validation;
 
 #[macro_use]
 extern crate diesel;
 
-use crate::command::Options;
-use diesel::prelude::*;
+use crate::{
+    aggregator::Sources,
+    command::{CommandKind, Options, PoliciesCommand},
+    energy::Energy,
+    metrics::Metrics,
+    push::Push,
+    sink::{influxdb::InfluxDb, json_lines::JsonLines, mqtt::Mqtt, Sink},
+    storage::{Journal, Storage},
+    validation::Validator,
+};
+use diesel::{Connection, PgConnection};
+use hub_database::continuous_aggregates;
 use human_panic::setup_panic;
+use std::{sync::Arc, time::Duration};
 use structopt::StructOpt;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -37,18 +58,178 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let database_url = options.database_url.unwrap_or(configuration.database_url);
 
-    if database_url.is_empty() {
+    let sinks_configuration = configuration.sinks;
+
+    // The database is needed by the subcommands, and by the
+    // PostgreSQL sink.
+    if database_url.is_empty() && (options.kind.is_some() || sinks_configuration.postgresql) {
         panic!(
             "The database URL is empty, use `--database-url` or the configuration file to set it"
         );
     }
 
-    let database_connection = PgConnection::establish(&database_url).expect(&format!(
-        "Failed to connect to database at `{}`",
-        database_url
-    ));
+    if let Some(CommandKind::Migrate) = options.kind {
+        let connection = PgConnection::establish(&database_url)?;
+        hub_database::migrate(&connection)?;
+        continuous_aggregates::create_daily(&connection, configuration.time_zone.name())?;
+
+        return Ok(());
+    }
+
+    if let Some(CommandKind::Policies(policies_command)) = options.kind {
+        let connection = PgConnection::establish(&database_url)?;
+        let policies = &configuration.policies;
+
+        match policies_command {
+            PoliciesCommand::Apply { tables } => policies::apply(&connection, policies, &tables)?,
+            PoliciesCommand::List => policies::list(&connection)?,
+            PoliciesCommand::Remove {
+                tables,
+                compression,
+                retention,
+            } => policies::remove(&connection, policies, &tables, compression, retention)?,
+        }
+
+        return Ok(());
+    }
+
+    if let Some(CommandKind::Export {
+        tables,
+        from,
+        to,
+        bucket,
+        format,
+        output,
+    }) = options.kind
+    {
+        let connection = PgConnection::establish(&database_url)?;
+
+        export::export(
+            &connection,
+            &tables,
+            &from,
+            to.as_deref(),
+            bucket.as_deref(),
+            configuration.time_zone.name(),
+            format,
+            &output,
+        )?;
+
+        return Ok(());
+    }
+
+    if let Some(CommandKind::Import { files }) = options.kind {
+        let connection = PgConnection::establish(&database_url)?;
+        continuous_aggregates::create_daily(&connection, configuration.time_zone.name())?;
+
+        import::import(
+            &connection,
+            &files,
+            configuration.time_zone,
+            &configuration.import.columns,
+            &configuration
+                .policies
+                .iter()
+                .filter_map(|(table, policy)| Some((table.clone(), policy.drop_after.clone()?)))
+                .collect(),
+        )?;
+
+        return Ok(());
+    }
+
+    let batch_size = options.batch_size.unwrap_or(configuration.batch_size);
+    let flush_interval = Duration::from_secs(
+        options
+            .flush_interval
+            .unwrap_or(configuration.flush_interval)
+            .into(),
+    );
+
+    let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+
+    if sinks_configuration.postgresql {
+        // The schema must be current before the first rows are stored.
+        let connection = PgConnection::establish(&database_url)?;
+        hub_database::migrate(&connection)?;
+        continuous_aggregates::create_daily(&connection, configuration.time_zone.name())?;
+
+        let journal_directory = match options
+            .journal_directory
+            .or(configuration.journal_directory)
+        {
+            Some(journal_directory) => journal_directory,
+            None => configuration::get_journal_directory()?,
+        };
+
+        sinks.push(Box::new(Storage::new(
+            database_url,
+            Journal::new(journal_directory)?,
+            batch_size,
+            flush_interval,
+            configuration
+                .policies
+                .into_iter()
+                .filter_map(|(table, policy)| Some((table, policy.drop_after?)))
+                .collect(),
+        )));
+    }
+
+    if let Some(mqtt) = sinks_configuration.mqtt {
+        sinks.push(Box::new(Mqtt::new(
+            mqtt.host,
+            mqtt.port,
+            mqtt.username
+                .map(|username| (username, mqtt.password.unwrap_or_default())),
+            mqtt.topic_prefix,
+        )));
+    }
+
+    if let Some(influxdb) = sinks_configuration.influxdb {
+        sinks.push(Box::new(InfluxDb::new(
+            influxdb.url,
+            influxdb.token,
+            batch_size,
+            flush_interval,
+        )));
+    }
+
+    if let Some(json_lines) = sinks_configuration.json_lines {
+        sinks.push(Box::new(JsonLines::new(&json_lines.path)?));
+    }
+
+    if sinks.is_empty() {
+        panic!("No sink is enabled, enable at least one in the `sinks` section of the configuration file");
+    }
+
+    let generic = options.generic || configuration.generic;
+    let websocket = options.websocket || configuration.websocket;
+    let discovery_refresh_rate = (options.discover || configuration.discover).then(|| {
+        options
+            .discovery_refresh_rate
+            .unwrap_or(configuration.discovery_refresh_rate)
+    });
+
+    let push = configuration.push.map(Push::new).transpose()?;
+
+    let metrics = Arc::new(Metrics::default());
+
+    if let Some(metrics_address) = options.metrics_address.or(configuration.metrics_address) {
+        metrics::serve(metrics_address, metrics.clone())?;
+    }
 
-    aggregator::aggregate(addresses.to_vec(), database_connection);
+    aggregator::aggregate(
+        Sources {
+            addresses: addresses.to_vec(),
+            discovery_refresh_rate,
+            push,
+        },
+        sinks,
+        metrics,
+        generic,
+        websocket,
+        Energy::new(configuration.time_zone),
+        Validator::new(configuration.validation),
+    );
 
     Ok(())
 }
@@ -0,0 +1,280 @@
+use crate::thing::generic;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Method, Response, Server};
+
+/// Metrics of a source, i.e. an address of WebThings.
+#[derive(Default)]
+struct Source {
+    fetches: u64,
+    errors: u64,
+    malformed_things: u64,
+    latency_sum: f64,
+    last_latency: f64,
+    /// Updates of subscribed things, and how long their changes have
+    /// been coalesced.
+    updates: u64,
+    update_delay_sum: f64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Latest value of every numeric property, indexed by thing ID and
+    /// property name.
+    properties: BTreeMap<(String, String), f64>,
+    sources: BTreeMap<SocketAddr, Source>,
+}
+
+/// Metrics of the aggregator, shared between the polling threads, the
+/// receiving loop, and the HTTP server.
+#[derive(Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    /// Record a successful fetch of a source, and how long it took.
+    pub fn fetched(&self, address: SocketAddr, latency: Duration) {
+        let mut registry = self.registry.lock().unwrap();
+        let source = registry.sources.entry(address).or_default();
+
+        source.fetches += 1;
+        source.latency_sum += latency.as_secs_f64();
+        source.last_latency = latency.as_secs_f64();
+    }
+
+    /// Record an update of a subscribed thing of a source, sent `delay`
+    /// after its first change has been received.
+    pub fn updated(&self, address: SocketAddr, delay: Duration) {
+        let mut registry = self.registry.lock().unwrap();
+        let source = registry.sources.entry(address).or_default();
+
+        source.updates += 1;
+        source.update_delay_sum += delay.as_secs_f64();
+    }
+
+    /// Record a failed fetch of a source.
+    pub fn failed(&self, address: SocketAddr) {
+        let mut registry = self.registry.lock().unwrap();
+
+        registry.sources.entry(address).or_default().errors += 1;
+    }
+
+    /// Record a thing that has been skipped because it's malformed.
+    pub fn malformed(&self, address: SocketAddr) {
+        let mut registry = self.registry.lock().unwrap();
+
+        registry
+            .sources
+            .entry(address)
+            .or_default()
+            .malformed_things += 1;
+    }
+
+    /// Update the latest values of the numeric properties of the
+    /// things. Booleans are `0` or `1`, other values are ignored.
+    pub fn update_properties(&self, things: &[generic::Thing]) {
+        let mut registry = self.registry.lock().unwrap();
+
+        for thing in things {
+            for (property_name, property) in &thing.properties {
+                let value = match &property.value {
+                    Some(Value::Number(value)) => value.as_f64(),
+                    Some(Value::Bool(value)) => Some(if *value { 1. } else { 0. }),
+                    _ => None,
+                };
+
+                if let Some(value) = value {
+                    registry
+                        .properties
+                        .insert((thing.id.clone(), property_name.clone()), value);
+                }
+            }
+        }
+    }
+
+    /// Render the metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut output = String::new();
+
+        header(
+            &mut output,
+            "webthing_property",
+            "gauge",
+            "Latest value of a numeric property of a thing.",
+        );
+
+        for ((thing_id, property_name), value) in &registry.properties {
+            let _ = writeln!(
+                output,
+                "webthing_property{{thing_id=\"{}\",property=\"{}\"}} {}",
+                escape(thing_id),
+                escape(property_name),
+                value
+            );
+        }
+
+        header(
+            &mut output,
+            "hub_event_aggregator_fetch_duration_seconds",
+            "summary",
+            "Time spent fetching a source successfully, when polling or subscribing.",
+        );
+
+        for (address, source) in &registry.sources {
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_fetch_duration_seconds_sum{{address=\"{}\"}} {}",
+                address, source.latency_sum
+            );
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_fetch_duration_seconds_count{{address=\"{}\"}} {}",
+                address, source.fetches
+            );
+        }
+
+        header(
+            &mut output,
+            "hub_event_aggregator_last_fetch_duration_seconds",
+            "gauge",
+            "Time spent by the last successful fetch of a source.",
+        );
+
+        for (address, source) in &registry.sources {
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_last_fetch_duration_seconds{{address=\"{}\"}} {}",
+                address, source.last_latency
+            );
+        }
+
+        header(
+            &mut output,
+            "hub_event_aggregator_update_delay_seconds",
+            "summary",
+            "Time between the first change of a subscribed thing and its update.",
+        );
+
+        for (address, source) in &registry.sources {
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_update_delay_seconds_sum{{address=\"{}\"}} {}",
+                address, source.update_delay_sum
+            );
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_update_delay_seconds_count{{address=\"{}\"}} {}",
+                address, source.updates
+            );
+        }
+
+        header(
+            &mut output,
+            "hub_event_aggregator_fetch_errors_total",
+            "counter",
+            "Number of failed fetches of a source.",
+        );
+
+        for (address, source) in &registry.sources {
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_fetch_errors_total{{address=\"{}\"}} {}",
+                address, source.errors
+            );
+        }
+
+        header(
+            &mut output,
+            "hub_event_aggregator_malformed_things_total",
+            "counter",
+            "Number of things skipped because they are malformed.",
+        );
+
+        for (address, source) in &registry.sources {
+            let _ = writeln!(
+                output,
+                "hub_event_aggregator_malformed_things_total{{address=\"{}\"}} {}",
+                address, source.malformed_things
+            );
+        }
+
+        output
+    }
+}
+
+/// Write the `HELP` and `TYPE` lines of a metric.
+fn header(output: &mut String, name: &str, r#type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, r#type);
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve the metrics on `GET /metrics`, in a background thread.
+pub fn serve(address: SocketAddr, metrics: Arc<Metrics>) -> Result<(), String> {
+    let server = Server::http(address)
+        .map_err(|error| format!("Failed to serve the metrics on `{}`: {}", address, error))?;
+
+    println!("Metrics are served on `http://{}/metrics`", address);
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match (request.method(), request.url()) {
+                (Method::Get, "/metrics") => Response::from_string(metrics.render()).with_header(
+                    "Content-Type: text/plain; version=0.0.4"
+                        .parse::<Header>()
+                        .unwrap(),
+                ),
+                _ => Response::from_string("Not found").with_status_code(404),
+            };
+
+            if let Err(error) = request.respond(response) {
+                eprintln!("Failed to respond to a metrics request: {}", error);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fetches_and_updates() {
+        let metrics = Metrics::default();
+        let address = "127.0.0.1:8888".parse().unwrap();
+
+        metrics.fetched(address, Duration::from_millis(500));
+        metrics.updated(address, Duration::from_millis(250));
+        metrics.updated(address, Duration::from_millis(1000));
+
+        let output = metrics.render();
+
+        assert!(output.contains(
+            "hub_event_aggregator_fetch_duration_seconds_count{address=\"127.0.0.1:8888\"} 1\n"
+        ));
+        assert!(output.contains(
+            "hub_event_aggregator_update_delay_seconds_sum{address=\"127.0.0.1:8888\"} 1.25\n"
+        ));
+        assert!(output.contains(
+            "hub_event_aggregator_update_delay_seconds_count{address=\"127.0.0.1:8888\"} 2\n"
+        ));
+    }
+}
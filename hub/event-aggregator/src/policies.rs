@@ -0,0 +1,221 @@
+use crate::{configuration::Policy, storage::TABLES};
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{Bool, Nullable, Text},
+};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("table `{0}` is not a hypertable of the hub")]
+    UnknownTable(String),
+
+    #[error("table `{0}` has no policy in the configuration file")]
+    NoPolicy(String),
+
+    #[error("failed to manage the policies: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// A policy, as listed by Timescale.
+#[derive(QueryableByName, Debug)]
+struct Job {
+    #[sql_type = "Text"]
+    hypertable_name: String,
+
+    #[sql_type = "Text"]
+    kind: String,
+
+    #[sql_type = "Nullable<Text>"]
+    after: Option<String>,
+
+    #[sql_type = "Text"]
+    schedule_interval: String,
+}
+
+fn check_table(table: &str) -> Result<(), Error> {
+    if TABLES.contains(&table) {
+        Ok(())
+    } else {
+        Err(Error::UnknownTable(table.to_string()))
+    }
+}
+
+#[derive(QueryableByName, Debug)]
+struct Hypertable {
+    #[sql_type = "Bool"]
+    compression_enabled: bool,
+}
+
+fn is_compression_enabled(connection: &PgConnection, table: &str) -> Result<bool, Error> {
+    Ok(sql_query(
+        "SELECT compression_enabled FROM timescaledb_information.hypertables WHERE hypertable_name = $1",
+    )
+    .bind::<Text, _>(table)
+    .load::<Hypertable>(connection)?
+    .first()
+    .is_some_and(|hypertable| hypertable.compression_enabled))
+}
+
+/// How compressed chunks are ordered and segmented: by time, and by
+/// property for `thing_property` where rows of many properties are
+/// interleaved.
+fn compression_settings(table: &str) -> (&'static str, Option<&'static str>) {
+    match table {
+        "thing_property" => ("time DESC", Some("thing_id, property_name")),
+        "weather_forecast" => ("fetched_at DESC, forecast_for", None),
+        _ => ("time DESC", None),
+    }
+}
+
+/// Apply the policies of the given tables, or of all the tables if
+/// none is given. Existing policies are replaced, and policies that
+/// are not set are removed.
+pub fn apply(
+    connection: &PgConnection,
+    policies: &BTreeMap<String, Policy>,
+    tables: &[String],
+) -> Result<(), Error> {
+    let tables = if tables.is_empty() {
+        policies.keys().cloned().collect()
+    } else {
+        tables.to_vec()
+    };
+
+    for table in &tables {
+        check_table(table)?;
+
+        let policy = policies
+            .get(table)
+            .ok_or_else(|| Error::NoPolicy(table.clone()))?;
+
+        remove_one(connection, table, true, true)?;
+
+        if let Some(compress_after) = &policy.compress_after {
+            // Compression settings cannot change once chunks are
+            // compressed, so they are set once.
+            if !is_compression_enabled(connection, table)? {
+                let (order_by, segment_by) = compression_settings(table);
+                let segment_by = segment_by
+                    .map(|segment_by| {
+                        format!(", timescaledb.compress_segmentby = '{}'", segment_by)
+                    })
+                    .unwrap_or_default();
+
+                sql_query(format!(
+                    "ALTER TABLE {} SET (timescaledb.compress, timescaledb.compress_orderby = '{}'{})",
+                    table, order_by, segment_by
+                ))
+                .execute(connection)?;
+            }
+
+            sql_query("SELECT add_compression_policy($1::text::regclass, $2::text::interval)")
+                .bind::<Text, _>(table)
+                .bind::<Text, _>(compress_after)
+                .execute(connection)?;
+
+            println!(
+                "Chunks of `{}` are compressed after {}",
+                table, compress_after
+            );
+        }
+
+        if let Some(drop_after) = &policy.drop_after {
+            sql_query("SELECT add_retention_policy($1::text::regclass, $2::text::interval)")
+                .bind::<Text, _>(table)
+                .bind::<Text, _>(drop_after)
+                .execute(connection)?;
+
+            println!("Chunks of `{}` are dropped after {}", table, drop_after);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the compression and retention policies of the database.
+pub fn list(connection: &PgConnection) -> Result<(), Error> {
+    let jobs = sql_query(
+        "SELECT \
+            hypertable_name::text, \
+            CASE proc_name WHEN 'policy_compression' THEN 'compression' ELSE 'retention' END AS kind, \
+            coalesce(config->>'compress_after', config->>'drop_after') AS after, \
+            schedule_interval::text \
+        FROM timescaledb_information.jobs \
+        WHERE proc_name IN ('policy_compression', 'policy_retention') \
+        ORDER BY hypertable_name, kind",
+    )
+    .load::<Job>(connection)?;
+
+    if jobs.is_empty() {
+        println!("No policy.");
+    }
+
+    for job in jobs {
+        println!(
+            "{}: {} after {} (checked every {})",
+            job.hypertable_name,
+            job.kind,
+            job.after.as_deref().unwrap_or("?"),
+            job.schedule_interval
+        );
+    }
+
+    Ok(())
+}
+
+/// Remove the compression and/or retention policies of the given
+/// tables, or of all the tables of the configuration file if none is
+/// given. Compressed chunks stay compressed.
+pub fn remove(
+    connection: &PgConnection,
+    policies: &BTreeMap<String, Policy>,
+    tables: &[String],
+    compression: bool,
+    retention: bool,
+) -> Result<(), Error> {
+    let tables = if tables.is_empty() {
+        policies.keys().cloned().collect()
+    } else {
+        tables.to_vec()
+    };
+
+    // Without any flag, all the policies are removed.
+    let (compression, retention) = if compression || retention {
+        (compression, retention)
+    } else {
+        (true, true)
+    };
+
+    for table in &tables {
+        check_table(table)?;
+        remove_one(connection, table, compression, retention)?;
+
+        println!("Policies of `{}` are removed", table);
+    }
+
+    Ok(())
+}
+
+fn remove_one(
+    connection: &PgConnection,
+    table: &str,
+    compression: bool,
+    retention: bool,
+) -> Result<(), Error> {
+    if compression {
+        sql_query("SELECT remove_compression_policy($1::text::regclass, if_exists => true)")
+            .bind::<Text, _>(table)
+            .execute(connection)?;
+    }
+
+    if retention {
+        sql_query("SELECT remove_retention_policy($1::text::regclass, if_exists => true)")
+            .bind::<Text, _>(table)
+            .execute(connection)?;
+    }
+
+    Ok(())
+}
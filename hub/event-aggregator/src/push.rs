@@ -0,0 +1,524 @@
+use crate::{aggregator::Message, configuration::PushConfiguration, thing::generic};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    net::SocketAddr,
+    path::Path,
+    sync::mpsc::Sender,
+    thread,
+};
+use thiserror::Error;
+use tiny_http::{Method, Request, Response, Server};
+
+/// Bodies larger than this are rejected.
+const MAXIMUM_BODY_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read the thing description `{0}`: {1}")]
+    Read(String, io::Error),
+
+    #[error("invalid thing description `{0}`: {1}")]
+    Description(String, serde_json::Error),
+
+    #[error("failed to listen on `{0}`: {1}")]
+    Listen(SocketAddr, String),
+}
+
+/// Property values of a thing, at a given time (now by default), as
+/// pushed in a JSON line.
+#[derive(Deserialize)]
+struct Sample {
+    id: String,
+    #[serde(default)]
+    time: Option<DateTime<Utc>>,
+    properties: Map<String, Value>,
+}
+
+/// Receive the property values that things push over HTTP, for the
+/// things that cannot be polled (e.g. a device that deep-sleeps most
+/// of the time). Two forms are accepted:
+///
+/// * `POST /things/<thing id>/properties`, with a property map, like
+///   `{"level": 42.5}`, as the body,
+/// * `POST /things`, with JSON lines, like `{"id": "<thing id>",
+///   "time": "2026-10-18T12:00:00Z", "properties": {"level": 42.5}}`,
+///   e.g. for values buffered by the device.
+///
+/// Requests must have the `Authorization: Bearer <token>` header. Only
+/// the things of the known descriptions are accepted, and the values
+/// are validated against them. Valid values go to the receiving loop,
+/// like the polled ones, with the listening address as their source.
+pub struct Push {
+    address: SocketAddr,
+    token: String,
+    /// Things indexed by their IDs, with their latest values.
+    things: HashMap<String, generic::Thing>,
+}
+
+impl Push {
+    /// Read the thing descriptions: each file has a thing, or an array
+    /// of things, as described by a WebThing server.
+    pub fn new(configuration: PushConfiguration) -> Result<Self, Error> {
+        let mut things = HashMap::new();
+
+        for path in &configuration.things {
+            for thing in read_descriptions(path)? {
+                things.insert(thing.id.clone(), thing);
+            }
+        }
+
+        Ok(Self {
+            address: configuration.address,
+            token: configuration.token,
+            things,
+        })
+    }
+
+    pub fn serve(mut self, tx: Sender<Message>) -> Result<(), Error> {
+        let server = Server::http(self.address)
+            .map_err(|error| Error::Listen(self.address, error.to_string()))?;
+
+        println!(
+            "Pushed values are received on `http://{}/things`",
+            self.address
+        );
+
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let (status_code, body) = match self.receive(&mut request) {
+                    Ok(samples) => {
+                        let number_of_samples = samples.len();
+
+                        if samples.into_iter().all(|thing| {
+                            tx.send(Message::Things {
+                                address: self.address,
+                                things: vec![thing],
+                            })
+                            .is_ok()
+                        }) {
+                            (200, format!("{} sample(s) received", number_of_samples))
+                        } else {
+                            (503, "The aggregator has stopped".to_string())
+                        }
+                    }
+
+                    Err(error) => {
+                        eprintln!(
+                            "Rejecting values pushed by `{}`: {}",
+                            request
+                                .remote_addr()
+                                .map_or_else(|| "?".to_string(), ToString::to_string),
+                            error.1
+                        );
+
+                        error
+                    }
+                };
+
+                if let Err(error) =
+                    request.respond(Response::from_string(body).with_status_code(status_code))
+                {
+                    eprintln!("Failed to respond to a push request: {}", error);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Read, validate and apply the pushed values. It returns a
+    /// snapshot of the thing for every sample, or the status code and
+    /// the reason of the rejection. Nothing is applied if a sample is
+    /// invalid.
+    fn receive(&mut self, request: &mut Request) -> Result<Vec<generic::Thing>, (u16, String)> {
+        if request.method() != &Method::Post {
+            return Err((405, "Only `POST` is allowed".to_string()));
+        }
+
+        let authorization = format!("Bearer {}", self.token);
+
+        if !request.headers().iter().any(|header| {
+            header.field.equiv("Authorization")
+                && constant_time_eq(header.value.as_bytes(), authorization.as_bytes())
+        }) {
+            return Err((401, "The token is missing or invalid".to_string()));
+        }
+
+        let mut body = String::new();
+
+        request
+            .as_reader()
+            .take(MAXIMUM_BODY_SIZE + 1)
+            .read_to_string(&mut body)
+            .map_err(|error| (400, format!("Failed to read the body: {}", error)))?;
+
+        if body.len() as u64 > MAXIMUM_BODY_SIZE {
+            return Err((413, "The body is too large".to_string()));
+        }
+
+        let url = request.url().trim_end_matches('/');
+
+        let samples = if url == "/things" {
+            body.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(nth, line)| {
+                    serde_json::from_str::<Sample>(line)
+                        .map_err(|error| (400, format!("Invalid line {}: {}", nth + 1, error)))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else if let Some(id) = url
+            .strip_prefix("/things/")
+            .and_then(|url| url.strip_suffix("/properties"))
+        {
+            vec![Sample {
+                id: id.to_string(),
+                time: None,
+                properties: serde_json::from_str(&body)
+                    .map_err(|error| (400, format!("Invalid property map: {}", error)))?,
+            }]
+        } else {
+            return Err((404, "Not found".to_string()));
+        };
+
+        for sample in &samples {
+            self.validate(sample)?;
+        }
+
+        Ok(samples
+            .into_iter()
+            .map(|sample| {
+                // Validated.
+                let thing = self.things.get_mut(&sample.id).unwrap();
+
+                for (property_name, value) in sample.properties {
+                    if let Some(property) = thing.properties.get_mut(&property_name) {
+                        property.value = Some(value);
+                    }
+                }
+
+                thing.fetched_at = sample.time.unwrap_or_else(Utc::now);
+
+                thing.clone()
+            })
+            .collect())
+    }
+
+    fn validate(&self, sample: &Sample) -> Result<(), (u16, String)> {
+        let thing = self
+            .things
+            .get(&sample.id)
+            .ok_or_else(|| (404, format!("Thing `{}` is unknown", sample.id)))?;
+
+        for (property_name, value) in &sample.properties {
+            let property = thing.properties.get(property_name).ok_or_else(|| {
+                (
+                    422,
+                    format!("Thing `{}` has no property `{}`", sample.id, property_name),
+                )
+            })?;
+
+            validate_value(property, value).map_err(|reason| {
+                (
+                    422,
+                    format!(
+                        "Property `{}` of thing `{}` {}",
+                        property_name, sample.id, reason
+                    ),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare in a time that doesn't depend on the position of the first
+/// difference, so that the token cannot be guessed byte after byte.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |difference, (left, right)| difference | (left ^ right))
+            == 0
+}
+
+fn read_descriptions(path: &Path) -> Result<Vec<generic::Thing>, Error> {
+    let content =
+        fs::read_to_string(path).map_err(|error| Error::Read(path.display().to_string(), error))?;
+    let description = |error| Error::Description(path.display().to_string(), error);
+
+    match serde_json::from_str::<Value>(&content).map_err(description)? {
+        things @ Value::Array(_) => serde_json::from_value(things).map_err(description),
+        thing => Ok(vec![serde_json::from_value(thing).map_err(description)?]),
+    }
+}
+
+/// Check a value against the description of its property: its type,
+/// its bounds, and its allowed values, when they are described.
+fn validate_value(property: &generic::Property, value: &Value) -> Result<(), String> {
+    if let Some(value_type) = &property.value_type {
+        let is_valid = match value_type.as_str() {
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "string" => value.is_string(),
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            _ => true,
+        };
+
+        if !is_valid {
+            return Err(format!(
+                "must be of type `{}`, given `{}`",
+                value_type, value
+            ));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = property.minimum.filter(|minimum| number < *minimum) {
+            return Err(format!(
+                "must be at least `{}`, given `{}`",
+                minimum, number
+            ));
+        }
+
+        if let Some(maximum) = property.maximum.filter(|maximum| number > *maximum) {
+            return Err(format!("must be at most `{}`, given `{}`", maximum, number));
+        }
+    }
+
+    if let Some(allowed_values) = &property.allowed_values {
+        if !allowed_values.contains(value) {
+            return Err(format!(
+                "must be one of `{}`, given `{}`",
+                allowed_values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("`, `"),
+                value
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tiny_http::{Header, TestRequest};
+
+    fn push() -> Push {
+        let tank: generic::Thing = serde_json::from_value(json!({
+            "id": "urn:dev:ops:rainwater-tank",
+            "title": "Rainwater tank",
+            "properties": {
+                "level": {
+                    "title": "Level",
+                    "type": "number",
+                    "unit": "percent",
+                    "minimum": 0,
+                    "maximum": 100,
+                    "value": null,
+                },
+                "pump": { "title": "Pump", "type": "boolean", "value": false },
+            },
+        }))
+        .unwrap();
+
+        Push {
+            address: "127.0.0.1:8095".parse().unwrap(),
+            token: "secret".to_string(),
+            things: [(tank.id.clone(), tank)].into(),
+        }
+    }
+
+    fn request(method: Method, path: &str, token: Option<&str>, body: &'static str) -> Request {
+        let mut request = TestRequest::new()
+            .with_method(method)
+            .with_path(path)
+            .with_body(body);
+
+        if let Some(token) = token {
+            request = request.with_header(
+                Header::from_bytes("Authorization", format!("Bearer {}", token)).unwrap(),
+            );
+        }
+
+        request.into()
+    }
+
+    fn status_code(result: Result<Vec<generic::Thing>, (u16, String)>) -> u16 {
+        match result {
+            Ok(_) => 200,
+            Err((status_code, _)) => status_code,
+        }
+    }
+
+    #[test]
+    fn test_only_post_is_allowed() {
+        let mut push = push();
+
+        for method in [Method::Get, Method::Put, Method::Delete] {
+            let mut request = request(method, "/things", Some("secret"), "");
+
+            assert_eq!(status_code(push.receive(&mut request)), 405);
+        }
+    }
+
+    #[test]
+    fn test_token() {
+        let mut push = push();
+        let body = r#"{"level": 42.5}"#;
+        let path = "/things/urn:dev:ops:rainwater-tank/properties";
+
+        for token in [
+            None,
+            Some(""),
+            Some("secre"),
+            Some("secret "),
+            Some("Secret"),
+        ] {
+            let mut request = request(Method::Post, path, token, body);
+
+            assert_eq!(
+                status_code(push.receive(&mut request)),
+                401,
+                "token {:?}",
+                token
+            );
+        }
+
+        let mut request = request(Method::Post, path, Some("secret"), body);
+        assert_eq!(status_code(push.receive(&mut request)), 200);
+
+        assert!(constant_time_eq(b"Bearer secret", b"Bearer secret"));
+        assert!(!constant_time_eq(b"Bearer secret", b"Bearer secreT"));
+        assert!(!constant_time_eq(b"Bearer ", b"Bearer secret"));
+    }
+
+    #[test]
+    fn test_empty_token_is_rejected() {
+        let configuration = |token: &str| {
+            serde_json::from_value::<PushConfiguration>(json!({
+                "address": "127.0.0.1:8095",
+                "token": token,
+                "things": [],
+            }))
+        };
+
+        assert!(configuration("").is_err());
+        assert!(configuration("  ").is_err());
+        assert!(configuration("secret").is_ok());
+    }
+
+    #[test]
+    fn test_property_map_to_snapshot() {
+        let mut push = push();
+        let before = Utc::now();
+
+        let mut request = request(
+            Method::Post,
+            "/things/urn:dev:ops:rainwater-tank/properties",
+            Some("secret"),
+            r#"{"level": 42.5}"#,
+        );
+        let things = push.receive(&mut request).unwrap();
+
+        assert_eq!(things.len(), 1);
+        assert_eq!(things[0].id, "urn:dev:ops:rainwater-tank");
+        assert_eq!(things[0].properties["level"].value, Some(json!(42.5)));
+        // The other properties are kept.
+        assert_eq!(things[0].properties["pump"].value, Some(json!(false)));
+        assert!(things[0].fetched_at >= before);
+    }
+
+    #[test]
+    fn test_json_lines_to_snapshots() {
+        let mut push = push();
+
+        let mut request = request(
+            Method::Post,
+            "/things",
+            Some("secret"),
+            concat!(
+                r#"{"id": "urn:dev:ops:rainwater-tank", "time": "2026-10-18T12:00:00Z", "properties": {"level": 40}}"#,
+                "\n\n",
+                r#"{"id": "urn:dev:ops:rainwater-tank", "time": "2026-10-18T12:05:00Z", "properties": {"pump": true}}"#,
+            ),
+        );
+        let things = push.receive(&mut request).unwrap();
+
+        // A snapshot per sample, with the values known at its time.
+        assert_eq!(things.len(), 2);
+        assert_eq!(
+            things[0].fetched_at,
+            "2026-10-18T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(things[0].properties["level"].value, Some(json!(40)));
+        assert_eq!(things[0].properties["pump"].value, Some(json!(false)));
+        assert_eq!(
+            things[1].fetched_at,
+            "2026-10-18T12:05:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(things[1].properties["level"].value, Some(json!(40)));
+        assert_eq!(things[1].properties["pump"].value, Some(json!(true)));
+    }
+
+    #[test]
+    fn test_invalid_samples() {
+        let mut push = push();
+
+        for (path, body, expected_status_code) in [
+            (
+                "/things/urn:dev:ops:unknown/properties",
+                r#"{"level": 1}"#,
+                404,
+            ),
+            (
+                "/things/urn:dev:ops:rainwater-tank/properties",
+                r#"{"volume": 1}"#,
+                422,
+            ),
+            (
+                "/things/urn:dev:ops:rainwater-tank/properties",
+                r#"{"level": 101}"#,
+                422,
+            ),
+            (
+                "/things/urn:dev:ops:rainwater-tank/properties",
+                r#"{"level": "high"}"#,
+                422,
+            ),
+            ("/things/urn:dev:ops:rainwater-tank/properties", "{", 400),
+            ("/things", r#"{"id": "urn:dev:ops:rainwater-tank"}"#, 400),
+            ("/elsewhere", "", 404),
+        ] {
+            let mut request = request(Method::Post, path, Some("secret"), body);
+
+            assert_eq!(
+                status_code(push.receive(&mut request)),
+                expected_status_code,
+                "{} {}",
+                path,
+                body
+            );
+        }
+
+        // Nothing has been applied.
+        assert_eq!(
+            push.things["urn:dev:ops:rainwater-tank"].properties["level"].value,
+            None
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use super::{Record, Sink};
+use crate::storage::Row;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// Write the rows to InfluxDB, with the [line
+/// protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/)
+/// over HTTP. Tables are measurements. Lines are buffered, and written
+/// in batches like the database rows. Lines that cannot be written are
+/// lost.
+pub struct InfluxDb {
+    client: Client,
+    url: String,
+    token: Option<String>,
+    buffer: Vec<String>,
+    batch_size: usize,
+    flush_interval: Duration,
+    last_flush: Instant,
+}
+
+impl InfluxDb {
+    pub fn new(
+        url: String,
+        token: Option<String>,
+        batch_size: usize,
+        flush_interval: Duration,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            token,
+            buffer: Vec::with_capacity(batch_size),
+            batch_size,
+            flush_interval,
+            last_flush: Instant::now(),
+        }
+    }
+
+    fn write(&self, body: String) -> Result<(), reqwest::Error> {
+        let mut request = self.client.post(&self.url).body(body);
+
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Token {}", token));
+        }
+
+        request.send()?.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+impl Sink for InfluxDb {
+    fn store(&mut self, rows: &[Row]) {
+        self.buffer
+            .extend(rows.iter().filter_map(|row| line(&Record::new(row))));
+
+        if self.buffer.len() >= self.batch_size || self.time_before_flush().is_zero() {
+            self.flush();
+        }
+    }
+
+    fn time_before_flush(&self) -> Duration {
+        self.flush_interval
+            .saturating_sub(self.last_flush.elapsed())
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let lines = std::mem::take(&mut self.buffer);
+        let number_of_lines = lines.len();
+
+        if let Err(error) = self.write(lines.join("\n")) {
+            eprintln!(
+                "Failed to write {} line(s) to InfluxDB, they are lost: {}",
+                number_of_lines, error
+            );
+        }
+    }
+}
+
+/// Format a record as a line: `<table>,<tags> <fields> <timestamp>`.
+/// Records without fields have no line.
+fn line(record: &Record) -> Option<String> {
+    if record.fields.is_empty() {
+        return None;
+    }
+
+    let mut line = escape(record.table, &[',', ' ']);
+
+    for (name, value) in &record.tags {
+        if !value.is_empty() {
+            let _ = write!(
+                line,
+                ",{}={}",
+                escape(name, &[',', '=', ' ']),
+                escape(value, &[',', '=', ' '])
+            );
+        }
+    }
+
+    for (nth, (name, value)) in record.fields.iter().enumerate() {
+        let value = match value {
+            // Numbers are always floats, so that a field never changes
+            // its type.
+            Value::Number(value) => value.as_f64()?.to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::String(value) => format!("\"{}\"", escape(value, &['"', '\\'])),
+            value => format!("\"{}\"", escape(&value.to_string(), &['"', '\\'])),
+        };
+
+        let _ = write!(
+            line,
+            "{}{}={}",
+            if nth == 0 { ' ' } else { ',' },
+            escape(name, &[',', '=', ' ']),
+            value
+        );
+    }
+
+    let time = &record.time;
+    let _ = write!(
+        line,
+        " {}",
+        time.timestamp() * 1_000_000_000 + i64::from(time.timestamp_subsec_nanos())
+    );
+
+    Some(line)
+}
+
+/// Escape the given characters with a backslash.
+fn escape(value: &str, characters: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for character in value.chars() {
+        if characters.contains(&character) {
+            escaped.push('\\');
+        }
+
+        escaped.push(character);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(tags: Vec<(&'static str, String)>, fields: Value) -> Record {
+        Record {
+            table: "thing_property",
+            time: "2026-06-01T10:00:00.5Z".parse().unwrap(),
+            tags,
+            fields: match fields {
+                Value::Object(fields) => fields,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_line() {
+        assert_eq!(
+            line(&record(
+                vec![
+                    ("thing_id", "urn:dev:ops:air".to_string()),
+                    ("unit", "".to_string()),
+                ],
+                json!({ "numeric_value": 45, "is_on": true }),
+            ))
+            .unwrap(),
+            "thing_property,thing_id=urn:dev:ops:air is_on=true,numeric_value=45 1780308000500000000"
+        );
+    }
+
+    #[test]
+    fn test_line_escaping() {
+        assert_eq!(
+            line(&record(
+                vec![("property_name", "a b,c=d".to_string())],
+                json!({
+                    "text value": "say \"hi\" \\o/",
+                    "json_value": { "a": "b" },
+                }),
+            ))
+            .unwrap(),
+            r#"thing_property,property_name=a\ b\,c\=d json_value="{\"a\":\"b\"}",text\ value="say \"hi\" \\o/" 1780308000500000000"#
+        );
+    }
+
+    #[test]
+    fn test_line_without_fields() {
+        assert_eq!(
+            line(&record(
+                vec![("thing_id", "urn:dev:ops:air".to_string())],
+                json!({})
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use super::Sink;
+use crate::storage::Row;
+use serde_json::Value;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Append the rows to a file, one JSON object per line, with the
+/// table name in `table`, e.g. `{"table":"air","time":"…",…}`.
+pub struct JsonLines {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl JsonLines {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: BufWriter::new(file),
+        })
+    }
+
+    fn write(&mut self, rows: &[Row]) -> io::Result<()> {
+        for row in rows {
+            let mut columns = row.columns();
+            columns.insert("table".to_string(), Value::from(row.table_name()));
+
+            serde_json::to_writer(&mut self.file, &columns)?;
+            self.file.write_all(b"\n")?;
+        }
+
+        self.file.flush()
+    }
+}
+
+impl Sink for JsonLines {
+    fn store(&mut self, rows: &[Row]) {
+        if let Err(error) = self.write(rows) {
+            eprintln!(
+                "Failed to write rows to `{}`, they are lost: {}",
+                self.path.display(),
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+pub mod influxdb;
+pub mod json_lines;
+pub mod mqtt;
+
+use crate::storage::Row;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::{net::SocketAddr, time::Duration};
+
+/// Where the aggregated rows go. The PostgreSQL database (see
+/// `Storage`) is one of them.
+pub trait Sink {
+    /// Store rows. They can be buffered until the sink is flushed.
+    fn store(&mut self, rows: &[Row]);
+
+    /// Time left before the buffered rows must be flushed.
+    fn time_before_flush(&self) -> Duration {
+        Duration::MAX
+    }
+
+    /// Write the buffered rows.
+    fn flush(&mut self) {}
+
+    /// Record the result of the last fetch of a source. Most sinks
+    /// don't care.
+    fn update_source_health(
+        &mut self,
+        _address: &SocketAddr,
+        _now: &DateTime<Utc>,
+        _result: Result<(), (&str, u32)>,
+    ) {
+    }
+}
+
+/// A row seen as a measurement: a time, tags that identify the series
+/// within the table, and fields that hold the values. Used by the
+/// sinks that are not aware of the database schema.
+pub struct Record {
+    pub table: &'static str,
+    pub time: DateTime<Utc>,
+    pub tags: Vec<(&'static str, String)>,
+    pub fields: Map<String, Value>,
+}
+
+impl Record {
+    pub fn new(row: &Row) -> Self {
+        let mut tags = Vec::new();
+
+        // A counter is stamped with its last update, and it is
+        // identified by its day: an interval crossing midnight updates
+        // two counters at once.
+        if let Row::EnergyCounter(counter) = row {
+            tags.push((
+                "day",
+                counter.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ));
+        }
+
+        let mut fields = row.columns();
+        fields.remove("time");
+        fields.remove("fetched_at");
+        fields.remove("updated_at");
+
+        let tag_names: &[&'static str] = match row {
+            Row::ThingProperty(_) => &["thing_id", "property_name", "unit", "property_type"],
+            Row::WeatherForecast(_) => &["forecast_for"],
+            Row::RejectedSample(_) => &["thing_id", "property_name"],
+            _ => &[],
+        };
+
+        tags.extend(
+            tag_names
+                .iter()
+                .filter_map(|name| match fields.remove(*name)? {
+                    Value::Null => None,
+                    Value::String(value) => Some((*name, value)),
+                    value => Some((*name, value.to_string())),
+                }),
+        );
+
+        fields.retain(|_, value| !value.is_null());
+
+        Self {
+            table: row.table_name(),
+            time: row.time(),
+            tags,
+            fields,
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| *tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hub_database::models;
+    use serde_json::json;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_record_of_a_thing_property() {
+        let record = Record::new(&Row::ThingProperty(models::ThingProperty {
+            time: utc("2026-06-01T10:00:00Z"),
+            thing_id: "urn:dev:ops:air".to_string(),
+            property_name: "inside_humidity".to_string(),
+            numeric_value: Some(45.),
+            text_value: None,
+            json_value: None,
+            unit: Some("percent".to_string()),
+            property_type: None,
+        }));
+
+        assert_eq!(record.table, "thing_property");
+        assert_eq!(record.time, utc("2026-06-01T10:00:00Z"));
+        assert_eq!(
+            record.tags,
+            [
+                ("thing_id", "urn:dev:ops:air".to_string()),
+                ("property_name", "inside_humidity".to_string()),
+                ("unit", "percent".to_string()),
+            ]
+        );
+        assert_eq!(record.tag("unit"), Some("percent"));
+        assert_eq!(record.tag("property_type"), None);
+
+        // The time is not a field, and nulls are dropped.
+        assert_eq!(
+            Value::Object(record.fields),
+            json!({ "numeric_value": 45. })
+        );
+    }
+
+    #[test]
+    fn test_record_of_an_energy_counter() {
+        let counter = |time: &str| {
+            Record::new(&Row::EnergyCounter(models::EnergyCounter {
+                time: utc(time),
+                updated_at: utc("2026-06-01T22:01:00Z"),
+                produced_energy: 10.,
+                consumed_energy: 0.,
+                charged_energy: 0.,
+                discharged_energy: 0.,
+            }))
+        };
+
+        let before_midnight = counter("2026-05-31T22:00:00Z");
+        let after_midnight = counter("2026-06-01T22:00:00Z");
+
+        // Both counters are updated at the same time, but they are
+        // told apart by their days.
+        assert_eq!(before_midnight.time, utc("2026-06-01T22:01:00Z"));
+        assert_eq!(after_midnight.time, before_midnight.time);
+        assert_eq!(before_midnight.tag("day"), Some("2026-05-31T22:00:00Z"));
+        assert_eq!(after_midnight.tag("day"), Some("2026-06-01T22:00:00Z"));
+
+        assert_eq!(
+            Value::Object(before_midnight.fields),
+            json!({
+                "produced_energy": 10.,
+                "consumed_energy": 0.,
+                "charged_energy": 0.,
+                "discharged_energy": 0.,
+            })
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Record, Sink};
+use crate::storage::Row;
+use rumqttc::{Client, MqttOptions, QoS};
+use serde_json::Value;
+use std::{thread, time::Duration};
+
+/// Capacity of the queue of messages waiting to be sent to the broker.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Delay before reconnecting to the broker.
+const RECONNECTION_DELAY: Duration = Duration::from_secs(10);
+
+/// Publish the latest values to an MQTT broker, as retained messages,
+/// one topic per property:
+///
+/// * `<prefix>/<thing id>/<property name>` for the rows of
+///   `thing_property`,
+/// * `<prefix>/<table>/<column>` for the other rows.
+///
+/// Payloads are the raw values, e.g. `42.5` or `idle`. Forecasts,
+/// energies and rejected samples are not published: they are not a
+/// state of the house.
+pub struct Mqtt {
+    client: Client,
+    topic_prefix: String,
+}
+
+impl Mqtt {
+    pub fn new(
+        host: String,
+        port: u16,
+        credentials: Option<(String, String)>,
+        topic_prefix: String,
+    ) -> Self {
+        let mut options = MqttOptions::new("hub-event-aggregator", host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some((username, password)) = credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut connection) = Client::new(options, QUEUE_CAPACITY);
+
+        // The connection must be polled for the messages to be sent.
+        // It reconnects by itself after a failure.
+        thread::spawn(move || {
+            for notification in connection.iter() {
+                if let Err(error) = notification {
+                    eprintln!(
+                        "MQTT connection failed, reconnecting in {:?}: {}",
+                        RECONNECTION_DELAY, error
+                    );
+
+                    thread::sleep(RECONNECTION_DELAY);
+                }
+            }
+        });
+
+        Self {
+            client,
+            topic_prefix,
+        }
+    }
+
+    fn messages(&self, record: &Record) -> Vec<(String, String)> {
+        let payload = |value: &Value| match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+
+        match (record.tag("thing_id"), record.tag("property_name")) {
+            (Some(thing_id), Some(property_name)) => record
+                .fields
+                .values()
+                .map(|value| {
+                    (
+                        format!(
+                            "{}/{}/{}",
+                            self.topic_prefix,
+                            topic_level(thing_id),
+                            topic_level(property_name)
+                        ),
+                        payload(value),
+                    )
+                })
+                .collect(),
+
+            _ => record
+                .fields
+                .iter()
+                .map(|(column, value)| {
+                    (
+                        format!("{}/{}/{}", self.topic_prefix, record.table, column),
+                        payload(value),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Sink for Mqtt {
+    fn store(&mut self, rows: &[Row]) {
+        let mut number_of_lost_messages = 0;
+        let mut last_error = None;
+
+        for row in rows {
+            if let Row::WeatherForecast(_) | Row::EnergyCounter(_) | Row::RejectedSample(_) = row {
+                continue;
+            }
+
+            for (topic, payload) in self.messages(&Record::new(row)) {
+                if let Err(error) = self
+                    .client
+                    .try_publish(topic, QoS::AtLeastOnce, true, payload)
+                {
+                    number_of_lost_messages += 1;
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        if let Some(error) = last_error {
+            eprintln!(
+                "Failed to publish {} MQTT message(s), they are lost: {}",
+                number_of_lost_messages, error
+            );
+        }
+    }
+}
+
+/// Replace the characters that cannot be used in a topic level.
+fn topic_level(value: &str) -> String {
+    value.replace(['/', '+', '#'], "_")
+}
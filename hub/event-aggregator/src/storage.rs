@@ -0,0 +1,849 @@
+use crate::sink::Sink;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use hub_database::{continuous_aggregates, models, schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Minimum delay between two attempts to connect to the database.
+const RECONNECTION_DELAY: Duration = Duration::from_secs(10);
+
+/// Number of journaled rows inserted per transaction when replaying.
+const REPLAY_CHUNK_SIZE: usize = 500;
+
+/// Maximum number of rows per `INSERT`, to stay below the maximum
+/// number of bind parameters per query of PostgreSQL.
+const MAXIMUM_ROWS_PER_INSERT: usize = 1000;
+
+/// Tables that can be journaled, in the order they are replayed.
+pub const TABLES: [&str; 12] = [
+    "electricity_production",
+    "electricity_storage",
+    "electricity_consumption",
+    "domestic_hot_water",
+    "air",
+    "ev_charging",
+    "vehicle",
+    "weather",
+    "weather_forecast",
+    "thing_property",
+    "energy_counter",
+    "rejected_samples",
+];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read or write the journal: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("failed to insert the journaled rows: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+/// A row to insert in one of the tables.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Row {
+    ElectricityProduction(models::ElectricityProduction),
+    ElectricityStorage(models::ElectricityStorage),
+    ElectricityConsumption(models::ElectricityConsumption),
+    DomesticHotWater(models::DomesticHotWater),
+    Air(models::Air),
+    EvCharging(models::EvCharging),
+    Vehicle(models::Vehicle),
+    Weather(models::Weather),
+    WeatherForecast(models::WeatherForecast),
+    ThingProperty(models::ThingProperty),
+    /// Energies to add to the counters of a day.
+    EnergyCounter(models::EnergyCounter),
+    RejectedSample(models::RejectedSample),
+}
+
+impl Row {
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            Self::ElectricityProduction(_) => "electricity_production",
+            Self::ElectricityStorage(_) => "electricity_storage",
+            Self::ElectricityConsumption(_) => "electricity_consumption",
+            Self::DomesticHotWater(_) => "domestic_hot_water",
+            Self::Air(_) => "air",
+            Self::EvCharging(_) => "ev_charging",
+            Self::Vehicle(_) => "vehicle",
+            Self::Weather(_) => "weather",
+            Self::WeatherForecast(_) => "weather_forecast",
+            Self::ThingProperty(_) => "thing_property",
+            Self::EnergyCounter(_) => "energy_counter",
+            Self::RejectedSample(_) => "rejected_samples",
+        }
+    }
+
+    /// When the row has been produced, i.e. when its thing has been
+    /// fetched.
+    pub fn time(&self) -> DateTime<Utc> {
+        match self {
+            Self::ElectricityProduction(row) => row.time,
+            Self::ElectricityStorage(row) => row.time,
+            Self::ElectricityConsumption(row) => row.time,
+            Self::DomesticHotWater(row) => row.time,
+            Self::Air(row) => row.time,
+            Self::EvCharging(row) => row.time,
+            Self::Vehicle(row) => row.time,
+            Self::Weather(row) => row.time,
+            Self::WeatherForecast(row) => row.fetched_at,
+            Self::ThingProperty(row) => row.time,
+            Self::EnergyCounter(row) => row.updated_at,
+            Self::RejectedSample(row) => row.time,
+        }
+    }
+
+    /// The columns of the row, indexed by their names.
+    pub fn columns(&self) -> Map<String, Value> {
+        // A row is serialized as `{"<variant>": {<columns>}}`.
+        match serde_json::to_value(self) {
+            Ok(Value::Object(row)) => match row.into_iter().next() {
+                Some((_, Value::Object(columns))) => columns,
+                _ => Map::new(),
+            },
+            _ => Map::new(),
+        }
+    }
+}
+
+/// Insert all the rows in a single transaction, with one multi-row
+/// `INSERT` per table (or more if there are too many rows). Rows that
+/// already exist are ignored. Energy counters are the exception:
+/// energies are added to the existing counters, so their rows must be
+/// inserted only once.
+pub fn insert_all<'a>(
+    connection: &PgConnection,
+    rows: impl IntoIterator<Item = &'a Row>,
+) -> QueryResult<()> {
+    let mut electricity_production = Vec::new();
+    let mut electricity_storage = Vec::new();
+    let mut electricity_consumption = Vec::new();
+    let mut domestic_hot_water = Vec::new();
+    let mut air = Vec::new();
+    let mut ev_charging = Vec::new();
+    let mut vehicle = Vec::new();
+    let mut weather = Vec::new();
+    let mut weather_forecast = Vec::new();
+    let mut thing_property = Vec::new();
+    let mut rejected_samples = Vec::new();
+    let mut energy_counter = BTreeMap::<_, models::EnergyCounter>::new();
+
+    for row in rows {
+        match row {
+            Row::ElectricityProduction(row) => electricity_production.push(row.clone()),
+            Row::ElectricityStorage(row) => electricity_storage.push(row.clone()),
+            Row::ElectricityConsumption(row) => electricity_consumption.push(row.clone()),
+            Row::DomesticHotWater(row) => domestic_hot_water.push(row.clone()),
+            Row::Air(row) => air.push(row.clone()),
+            Row::EvCharging(row) => ev_charging.push(row.clone()),
+            Row::Vehicle(row) => vehicle.push(row.clone()),
+            Row::Weather(row) => weather.push(row.clone()),
+            Row::WeatherForecast(row) => weather_forecast.push(row.clone()),
+            Row::ThingProperty(row) => thing_property.push(row.clone()),
+            Row::RejectedSample(row) => rejected_samples.push(row.clone()),
+
+            // A day can be updated only once per `INSERT`, so energies
+            // are summed per day first.
+            Row::EnergyCounter(row) => {
+                let counter =
+                    energy_counter
+                        .entry(row.time)
+                        .or_insert_with(|| models::EnergyCounter {
+                            produced_energy: 0.,
+                            consumed_energy: 0.,
+                            charged_energy: 0.,
+                            discharged_energy: 0.,
+                            ..row.clone()
+                        });
+
+                counter.updated_at = counter.updated_at.max(row.updated_at);
+                counter.produced_energy += row.produced_energy;
+                counter.consumed_energy += row.consumed_energy;
+                counter.charged_energy += row.charged_energy;
+                counter.discharged_energy += row.discharged_energy;
+            }
+        }
+    }
+
+    let energy_counter = energy_counter.into_values().collect::<Vec<_>>();
+
+    macro_rules! insert {
+        ($table:ident) => {
+            for chunk in $table.chunks(MAXIMUM_ROWS_PER_INSERT) {
+                diesel::insert_into(schema::$table::table)
+                    .values(chunk)
+                    .on_conflict_do_nothing()
+                    .execute(connection)?;
+            }
+        };
+    }
+
+    connection.transaction(|| {
+        insert!(electricity_production);
+        insert!(electricity_storage);
+        insert!(electricity_consumption);
+        insert!(domestic_hot_water);
+        insert!(air);
+        insert!(ev_charging);
+        insert!(vehicle);
+        insert!(weather);
+        insert!(weather_forecast);
+        insert!(thing_property);
+        insert!(rejected_samples);
+
+        for chunk in energy_counter.chunks(MAXIMUM_ROWS_PER_INSERT) {
+            use diesel::pg::upsert::excluded;
+            use schema::energy_counter::dsl;
+
+            diesel::insert_into(dsl::energy_counter)
+                .values(chunk)
+                .on_conflict(dsl::time)
+                .do_update()
+                .set((
+                    dsl::updated_at.eq(excluded(dsl::updated_at)),
+                    dsl::produced_energy.eq(dsl::produced_energy + excluded(dsl::produced_energy)),
+                    dsl::consumed_energy.eq(dsl::consumed_energy + excluded(dsl::consumed_energy)),
+                    dsl::charged_energy.eq(dsl::charged_energy + excluded(dsl::charged_energy)),
+                    dsl::discharged_energy
+                        .eq(dsl::discharged_energy + excluded(dsl::discharged_energy)),
+                ))
+                .execute(connection)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// An on-disk, append-only, journal of rows, one file per table. Rows
+/// that cannot be inserted in the database are appended to the
+/// journal, and replayed in order once the database is reachable
+/// again.
+///
+/// While a journal is replayed, a cursor (the `<table>.cursor` file)
+/// records up to where its rows have been inserted, so that an
+/// interrupted replay resumes after them instead of inserting them
+/// again.
+pub struct Journal {
+    directory: PathBuf,
+}
+
+impl Journal {
+    pub fn new(directory: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+
+        let journal = Self { directory };
+
+        // A cursor without its journal is left by a replay interrupted
+        // right after the journal has been removed. It must not apply to
+        // the next journal.
+        for table_name in TABLES {
+            if !journal.path(table_name).exists() {
+                fs::remove_file(journal.cursor_path(table_name)).or_else(ignore_not_found)?;
+            }
+        }
+
+        Ok(journal)
+    }
+
+    fn path(&self, table_name: &str) -> PathBuf {
+        self.directory.join(format!("{}.jsonl", table_name))
+    }
+
+    fn cursor_path(&self, table_name: &str) -> PathBuf {
+        self.directory.join(format!("{}.cursor", table_name))
+    }
+
+    fn rejected_path(&self, table_name: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}.rejected.jsonl", table_name))
+    }
+
+    /// Append a row to the journal of its table.
+    pub fn append(&self, row: &Row) -> io::Result<()> {
+        let mut line = serde_json::to_string(row)?;
+        line.push('\n');
+
+        append_line(&self.path(row.table_name()), &line)
+    }
+
+    /// Insert all the journaled rows, table by table, in the order they
+    /// have been journaled. The journal of a table is removed once all
+    /// its rows have been inserted.
+    ///
+    /// Lines that cannot be read, and rows that the database refuses,
+    /// are moved to the `<table>.rejected.jsonl` file, so that they
+    /// don't block the journal forever. It fails only if the journal
+    /// cannot be read, or if the database is not reachable anymore.
+    pub fn replay(&self, connection: &PgConnection) -> Result<(), Error> {
+        self.replay_with(
+            |rows| insert_all(connection, rows),
+            || connection.execute("SELECT 1").is_ok(),
+        )
+    }
+
+    /// Same as `replay`, with `insert` to insert rows, and
+    /// `is_reachable` to check whether an insertion has failed because
+    /// of the rows or because of the database.
+    fn replay_with<I, R>(&self, mut insert: I, is_reachable: R) -> Result<(), Error>
+    where
+        I: FnMut(&[Row]) -> QueryResult<()>,
+        R: Fn() -> bool,
+    {
+        for table_name in TABLES {
+            let path = self.path(table_name);
+
+            if !path.exists() {
+                continue;
+            }
+
+            let mut number_of_rows = 0;
+            let mut lines = Vec::with_capacity(REPLAY_CHUNK_SIZE);
+
+            // Rows before the cursor have already been inserted by a
+            // previous replay that has been interrupted.
+            let mut offset = self.cursor(table_name)?;
+            let mut reader = BufReader::new(File::open(&path)?);
+            reader.seek(SeekFrom::Start(offset))?;
+
+            let mut line = String::new();
+
+            loop {
+                line.clear();
+
+                match reader.read_line(&mut line)? {
+                    0 => break,
+                    length => offset += length as u64,
+                }
+
+                let line = line.trim_end_matches('\n');
+
+                match serde_json::from_str::<Row>(line) {
+                    Ok(row) => lines.push((offset, line.to_string(), row)),
+
+                    // A line can be truncated if the program has been
+                    // killed while writing it, or be written in an older
+                    // format.
+                    Err(error) => {
+                        eprintln!(
+                            "Rejecting an invalid row in the journal of `{}`: {}",
+                            table_name, error
+                        );
+
+                        self.reject(table_name, line)?;
+                    }
+                }
+
+                if lines.len() == REPLAY_CHUNK_SIZE {
+                    number_of_rows +=
+                        self.insert_chunk(table_name, &lines, &mut insert, &is_reachable)?;
+                    lines.clear();
+                }
+            }
+
+            number_of_rows += self.insert_chunk(table_name, &lines, &mut insert, &is_reachable)?;
+
+            fs::remove_file(&path)?;
+            fs::remove_file(self.cursor_path(table_name)).or_else(ignore_not_found)?;
+
+            println!(
+                "Replayed {} row(s) from the journal of `{}`",
+                number_of_rows, table_name
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Insert a chunk of journaled rows, with their lines and the
+    /// offsets of their ends. If the chunk is refused while the
+    /// database is reachable, its rows are inserted one by one, and the
+    /// refused ones are rejected. The cursor is moved after each
+    /// insertion, since energy counters must not be incremented twice.
+    /// Returns the number of inserted rows.
+    fn insert_chunk<I, R>(
+        &self,
+        table_name: &str,
+        lines: &[(u64, String, Row)],
+        insert: &mut I,
+        is_reachable: &R,
+    ) -> Result<usize, Error>
+    where
+        I: FnMut(&[Row]) -> QueryResult<()>,
+        R: Fn() -> bool,
+    {
+        let (end, _, _) = match lines.last() {
+            Some(last) => last,
+            None => return Ok(0),
+        };
+
+        let rows = lines
+            .iter()
+            .map(|(_, _, row)| row.clone())
+            .collect::<Vec<_>>();
+
+        let error = match insert(&rows) {
+            Ok(()) => {
+                self.move_cursor(table_name, *end)?;
+
+                return Ok(rows.len());
+            }
+            Err(error) => error,
+        };
+
+        if !is_reachable() {
+            return Err(error.into());
+        }
+
+        let mut number_of_rows = 0;
+
+        for (end, line, row) in lines {
+            match insert(std::slice::from_ref(row)) {
+                Ok(()) => number_of_rows += 1,
+
+                Err(error) if is_reachable() => {
+                    eprintln!(
+                        "Rejecting a row in the journal of `{}` refused by the database: {}",
+                        table_name, error
+                    );
+
+                    self.reject(table_name, line)?;
+                }
+
+                Err(error) => return Err(error.into()),
+            }
+
+            self.move_cursor(table_name, *end)?;
+        }
+
+        Ok(number_of_rows)
+    }
+
+    /// The offset in the journal of a table up to which rows have
+    /// been inserted.
+    fn cursor(&self, table_name: &str) -> io::Result<u64> {
+        match fs::read_to_string(self.cursor_path(table_name)) {
+            Ok(cursor) => cursor
+                .trim()
+                .parse()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(0),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Move the cursor of the journal of a table. The cursor is
+    /// replaced atomically, so that it's never half-written.
+    fn move_cursor(&self, table_name: &str, offset: u64) -> io::Result<()> {
+        let path = self.cursor_path(table_name);
+        let temporary_path = path.with_extension("cursor.tmp");
+
+        fs::write(&temporary_path, offset.to_string())?;
+        fs::rename(temporary_path, path)
+    }
+
+    /// Move a line of the journal of a table to its rejected file.
+    fn reject(&self, table_name: &str, line: &str) -> io::Result<()> {
+        append_line(&self.rejected_path(table_name), &format!("{}\n", line))
+    }
+}
+
+fn ignore_not_found(error: io::Error) -> io::Result<()> {
+    if error.kind() == io::ErrorKind::NotFound {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Where the rows are stored: in the database when it's reachable, in
+/// the journal otherwise. Rows are buffered, and flushed in batches
+/// when the buffer is full or when the flush interval has elapsed.
+pub struct Storage {
+    database_url: String,
+    connection: Option<PgConnection>,
+    last_connection_attempt: Option<Instant>,
+    journal: Journal,
+    buffer: Vec<Row>,
+    batch_size: usize,
+    flush_interval: Duration,
+    last_flush: Instant,
+    drop_after: BTreeMap<String, String>,
+}
+
+impl Storage {
+    pub fn new(
+        database_url: String,
+        journal: Journal,
+        batch_size: usize,
+        flush_interval: Duration,
+        drop_after: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            database_url,
+            connection: None,
+            last_connection_attempt: None,
+            journal,
+            buffer: Vec::with_capacity(batch_size),
+            batch_size,
+            flush_interval,
+            last_flush: Instant::now(),
+            drop_after,
+        }
+    }
+
+    /// Get a connection to the database. If there is none, a new
+    /// connection is established (at most once per
+    /// `RECONNECTION_DELAY`), the journal is replayed, and the
+    /// continuous aggregates are refreshed, before the connection is
+    /// handed out.
+    pub fn connection(&mut self) -> Option<&PgConnection> {
+        if self.connection.is_none() {
+            if matches!(self.last_connection_attempt, Some(time) if time.elapsed() < RECONNECTION_DELAY)
+            {
+                return None;
+            }
+
+            self.last_connection_attempt = Some(Instant::now());
+
+            let connection = match PgConnection::establish(&self.database_url) {
+                Ok(connection) => connection,
+                Err(error) => {
+                    eprintln!(
+                        "Failed to connect to the database at `{}`: {}",
+                        self.database_url, error
+                    );
+
+                    return None;
+                }
+            };
+
+            // Refused rows are rejected by the replay, so it fails only
+            // if the journal cannot be read, which must not prevent the
+            // new rows from being inserted, or if the database is
+            // unreachable again.
+            match self.journal.replay(&connection) {
+                Ok(()) => (),
+
+                Err(error @ Error::Io(_)) => {
+                    eprintln!("Failed to replay the journal: {}", error)
+                }
+
+                Err(error @ Error::Database(_)) => {
+                    eprintln!("Failed to replay the journal: {}", error);
+
+                    return None;
+                }
+            }
+
+            if let Err(error) = continuous_aggregates::refresh(&connection, &self.drop_after) {
+                eprintln!("Failed to refresh the continuous aggregates: {}", error);
+            }
+
+            self.connection = Some(connection);
+        }
+
+        self.connection.as_ref()
+    }
+
+    /// Drop the current connection, e.g. after a failure, so that a new
+    /// one is established on next use.
+    pub fn disconnect(&mut self) {
+        self.connection = None;
+    }
+}
+
+impl Sink for Storage {
+    /// Buffer rows, and flush them if the buffer is full or if the
+    /// flush interval has elapsed.
+    fn store(&mut self, rows: &[Row]) {
+        self.buffer.extend_from_slice(rows);
+
+        if self.buffer.len() >= self.batch_size || self.time_before_flush().is_zero() {
+            self.flush();
+        }
+    }
+
+    /// Time left before the buffer must be flushed.
+    fn time_before_flush(&self) -> Duration {
+        self.flush_interval
+            .saturating_sub(self.last_flush.elapsed())
+    }
+
+    /// Insert the buffered rows in the database. If it fails, the rows
+    /// are appended to the journal.
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let rows = std::mem::take(&mut self.buffer);
+
+        if let Some(connection) = self.connection() {
+            match insert_all(connection, &rows) {
+                Ok(()) => return,
+                Err(error) => {
+                    eprintln!("Failed to insert rows, journaling them: {}", error);
+
+                    self.disconnect();
+                }
+            }
+        }
+
+        for row in &rows {
+            if let Err(error) = self.journal.append(row) {
+                eprintln!(
+                    "Failed to journal a row of `{}`, it is lost: {}",
+                    row.table_name(),
+                    error
+                );
+            }
+        }
+    }
+
+    /// Record the result of the last fetch of a source in the
+    /// `source_health` table. Nothing is recorded if the database is
+    /// unreachable.
+    fn update_source_health(
+        &mut self,
+        address: &SocketAddr,
+        now: &DateTime<Utc>,
+        result: Result<(), (&str, u32)>,
+    ) {
+        use diesel::pg::upsert::excluded;
+        use schema::source_health::dsl::{self, source_health};
+
+        let address = address.to_string();
+
+        let connection = match self.connection() {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let result = match result {
+            Ok(()) => diesel::insert_into(source_health)
+                .values(&models::SourceHealth {
+                    address: address.clone(),
+                    last_success_time: Some(*now),
+                    last_error_time: None,
+                    last_error: None,
+                    consecutive_failures: 0,
+                })
+                .on_conflict(dsl::address)
+                .do_update()
+                .set((
+                    dsl::last_success_time.eq(excluded(dsl::last_success_time)),
+                    dsl::consecutive_failures.eq(0),
+                ))
+                .execute(connection),
+
+            Err((error, consecutive_failures)) => diesel::insert_into(source_health)
+                .values(&models::SourceHealth {
+                    address: address.clone(),
+                    last_success_time: None,
+                    last_error_time: Some(*now),
+                    last_error: Some(error.to_string()),
+                    consecutive_failures: consecutive_failures.try_into().unwrap_or(i32::MAX),
+                })
+                .on_conflict(dsl::address)
+                .do_update()
+                .set((
+                    dsl::last_error_time.eq(excluded(dsl::last_error_time)),
+                    dsl::last_error.eq(excluded(dsl::last_error)),
+                    dsl::consecutive_failures.eq(excluded(dsl::consecutive_failures)),
+                ))
+                .execute(connection),
+        };
+
+        if let Err(error) = result {
+            eprintln!("Failed to update the health of `{}`: {}", address, error);
+
+            self.disconnect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thing_property(thing_id: &str) -> Row {
+        Row::ThingProperty(models::ThingProperty {
+            time: Utc::now(),
+            thing_id: thing_id.to_string(),
+            property_name: "power".to_string(),
+            numeric_value: Some(42.),
+            text_value: None,
+            json_value: None,
+            unit: Some("watt".to_string()),
+            property_type: Some("number".to_string()),
+        })
+    }
+
+    fn thing_id(row: &Row) -> &str {
+        match row {
+            Row::ThingProperty(row) => &row.thing_id,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_lines_and_rows() {
+        let directory = std::env::temp_dir().join(format!(
+            "hub-event-aggregator-journal-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+
+        let journal = Journal::new(directory.clone()).unwrap();
+
+        journal.append(&thing_property("a")).unwrap();
+        append_line(
+            &journal.path("thing_property"),
+            "{\"ThingProperty\": {\"trunc\n",
+        )
+        .unwrap();
+        journal.append(&thing_property("poisoned")).unwrap();
+        journal.append(&thing_property("b")).unwrap();
+
+        let mut inserted = Vec::new();
+
+        journal
+            .replay_with(
+                |rows| {
+                    if rows.iter().any(|row| thing_id(row) == "poisoned") {
+                        return Err(diesel::result::Error::RollbackTransaction);
+                    }
+
+                    inserted.extend(rows.iter().map(|row| thing_id(row).to_string()));
+
+                    Ok(())
+                },
+                || true,
+            )
+            .unwrap();
+
+        assert_eq!(inserted, ["a", "b"]);
+        assert!(!journal.path("thing_property").exists());
+
+        let rejected = fs::read_to_string(journal.rejected_path("thing_property")).unwrap();
+        let rejected = rejected.lines().collect::<Vec<_>>();
+
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[0], "{\"ThingProperty\": {\"trunc");
+        assert!(rejected[1].contains("\"poisoned\""));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_replay_keeps_the_journal_when_unreachable() {
+        let directory = std::env::temp_dir().join(format!(
+            "hub-event-aggregator-journal-unreachable-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+
+        let journal = Journal::new(directory.clone()).unwrap();
+        journal.append(&thing_property("a")).unwrap();
+
+        let result = journal.replay_with(
+            |_| Err(diesel::result::Error::RollbackTransaction),
+            || false,
+        );
+
+        assert!(matches!(result, Err(Error::Database(_))));
+        assert!(journal.path("thing_property").exists());
+        assert!(!journal.rejected_path("thing_property").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_replay_resumes_after_the_cursor() {
+        let directory = std::env::temp_dir().join(format!(
+            "hub-event-aggregator-journal-interrupted-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+
+        let journal = Journal::new(directory.clone()).unwrap();
+
+        for index in 0..REPLAY_CHUNK_SIZE + 100 {
+            journal
+                .append(&Row::EnergyCounter(models::EnergyCounter {
+                    time: Utc::now(),
+                    updated_at: Utc::now(),
+                    produced_energy: index as f64,
+                    consumed_energy: 0.,
+                    charged_energy: 0.,
+                    discharged_energy: 0.,
+                }))
+                .unwrap();
+        }
+
+        let mut inserted = 0;
+
+        // The database becomes unreachable after the first chunk.
+        let result = journal.replay_with(
+            |rows| {
+                if inserted > 0 {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+
+                inserted += rows.len();
+
+                Ok(())
+            },
+            || false,
+        );
+
+        assert!(matches!(result, Err(Error::Database(_))));
+        assert_eq!(inserted, REPLAY_CHUNK_SIZE);
+
+        let mut produced_energies = Vec::new();
+
+        journal
+            .replay_with(
+                |rows| {
+                    produced_energies.extend(rows.iter().map(|row| match row {
+                        Row::EnergyCounter(row) => row.produced_energy as usize,
+                        _ => unreachable!(),
+                    }));
+
+                    Ok(())
+                },
+                || true,
+            )
+            .unwrap();
+
+        assert_eq!(
+            produced_energies,
+            (REPLAY_CHUNK_SIZE..REPLAY_CHUNK_SIZE + 100).collect::<Vec<_>>()
+        );
+        assert!(!journal.path("energy_counter").exists());
+        assert!(!journal.cursor_path("energy_counter").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{aggregator::Message, metrics::Metrics, thing::generic};
+use chrono::Utc;
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tungstenite::{stream::MaybeTlsStream, Message as Frame};
+
+/// A WebThing server notifies each property change separately. Changes
+/// are coalesced until no new change has been received for this delay…
+const COALESCING_DELAY: Duration = Duration::from_millis(250);
+
+/// … or until this delay has elapsed since the first change.
+const MAXIMUM_COALESCING_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("there is no thing to subscribe to")]
+    NoThing,
+
+    #[error("failed to connect to `{0}`: {1}")]
+    Connect(String, tungstenite::Error),
+
+    #[error("failed to configure `{0}`: {1}")]
+    Configure(String, io::Error),
+
+    #[error("WebSocket `{0}` has dropped: {1}")]
+    Dropped(String, tungstenite::Error),
+
+    #[error("WebSocket `{0}` has been closed by the server")]
+    Closed(String),
+
+    #[error("the subscription has been stopped")]
+    Stopped,
+}
+
+/// Open a WebSocket to each thing, and send a thing to the receiving
+/// loop every time its properties change (i.e. on `propertyStatus`
+/// messages). The other things are not sent again, as they have not
+/// changed. It blocks until one of the WebSockets drops, which closes
+/// all the others, or until `stop_polling` is set, and returns why.
+pub fn subscribe(
+    address: SocketAddr,
+    things: Vec<generic::Thing>,
+    tx: &Sender<Message>,
+    metrics: &Arc<Metrics>,
+    stop_polling: &Arc<AtomicBool>,
+) -> Error {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (done_tx, done_rx) = channel();
+
+    for thing in things {
+        let tx = tx.clone();
+        let metrics = metrics.clone();
+        let stop = stop.clone();
+        let stop_polling = stop_polling.clone();
+        let done_tx = done_tx.clone();
+
+        thread::spawn(move || {
+            let error = subscribe_thing(address, thing, &tx, &metrics, &[&stop, &stop_polling]);
+            stop.store(true, Ordering::Relaxed);
+
+            let _ = done_tx.send(error);
+        });
+    }
+
+    drop(done_tx);
+
+    let error = done_rx.recv().unwrap_or(Error::NoThing);
+
+    // Wait for all the other WebSockets to be closed.
+    for _ in done_rx.iter() {}
+
+    error
+}
+
+/// Subscribe to `thing` until one of the `stops` flags is set.
+fn subscribe_thing(
+    address: SocketAddr,
+    mut thing: generic::Thing,
+    tx: &Sender<Message>,
+    metrics: &Metrics,
+    stops: &[&AtomicBool],
+) -> Error {
+    let url = thing.websocket_url();
+
+    let mut socket = match tungstenite::connect(&url) {
+        Ok((socket, _response)) => socket,
+        Err(error) => return Error::Connect(url, error),
+    };
+
+    // Reads must time out, so that coalesced changes are sent, and so that
+    // the `stops` flags are checked regularly.
+    if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+        if let Err(error) = stream.set_read_timeout(Some(COALESCING_DELAY)) {
+            return Error::Configure(url, error);
+        }
+    }
+
+    let mut first_change_time: Option<Instant> = None;
+
+    loop {
+        if stops.iter().any(|stop| stop.load(Ordering::Relaxed)) {
+            let _ = socket.close(None);
+
+            return Error::Stopped;
+        }
+
+        let has_timed_out = match socket.read() {
+            Ok(Frame::Text(text)) => {
+                match serde_json::from_str::<generic::WebSocketMessage>(&text) {
+                    Ok(message) if message.is_property_status() => {
+                        for (property_name, value) in message.data {
+                            if let Some(property) = thing.properties.get_mut(&property_name) {
+                                property.value.replace(value);
+                                thing.fetched_at = Utc::now();
+                                first_change_time.get_or_insert_with(Instant::now);
+                            }
+                        }
+                    }
+
+                    Ok(_) => (),
+
+                    Err(error) => {
+                        eprintln!("Ignoring invalid message from `{}`: {}", url, error)
+                    }
+                }
+
+                false
+            }
+
+            Ok(Frame::Close(_)) => return Error::Closed(url),
+
+            Ok(_) => false,
+
+            Err(tungstenite::Error::Io(error))
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                true
+            }
+
+            Err(error) => return Error::Dropped(url, error),
+        };
+
+        if let Some(time) = first_change_time {
+            if has_timed_out || time.elapsed() >= MAXIMUM_COALESCING_DELAY {
+                first_change_time = None;
+
+                let message = Message::Things {
+                    address,
+                    things: vec![thing.clone()],
+                };
+
+                if tx.send(message).is_err() {
+                    return Error::Stopped;
+                }
+
+                metrics.updated(address, time.elapsed());
+            }
+        }
+    }
+}
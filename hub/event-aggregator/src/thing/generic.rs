@@ -1,23 +1,59 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Thing {
     pub id: String,
+    /// Things that push their values have no base.
+    #[serde(default)]
     pub base: String,
     pub title: String,
     ///#[serde(rename(deserialize = "@type"))]
     ///pub capabilities: Vec<Capability>,
     pub properties: HashMap<String, Property>,
+    #[serde(default)]
+    pub links: Vec<Link>,
+    /// When the property values have been fetched.
+    #[serde(skip, default = "Utc::now")]
+    pub fetched_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Thing {
+    /// The URL of the WebSocket of the thing, read from the
+    /// `alternate` link, or guessed from `base`.
+    pub fn websocket_url(&self) -> String {
+        self.links
+            .iter()
+            .find(|link| link.rel.as_deref() == Some("alternate") && link.href.starts_with("ws"))
+            .map(|link| link.href.clone())
+            .unwrap_or_else(|| self.base.replacen("http", "ws", 1))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Link {
+    pub rel: Option<String>,
+    pub href: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Property {
     pub title: String,
-    #[serde(rename(deserialize = "@type"))]
-    pub r#type: PropertyType,
-    //pub unit: String,
+    #[serde(rename(deserialize = "@type"), default)]
+    pub r#type: Option<PropertyType>,
+    pub unit: Option<String>,
+    /// The JSON type of the value, e.g. `number` or `boolean`.
+    #[serde(rename(deserialize = "type"), default)]
+    pub value_type: Option<String>,
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub maximum: Option<f64>,
+    /// The allowed values.
+    #[serde(rename(deserialize = "enum"), default)]
+    pub allowed_values: Option<Vec<Value>>,
     //#[serde(rename(deserialize = "readOnly"))]
     //pub read_only: bool,
     pub value: Option<PropertyValue>,
@@ -25,6 +61,23 @@ pub struct Property {
 
 pub type PropertyValue = Value;
 
+// https://webthings.io/api/#web-socket-api
+#[derive(Debug, Deserialize)]
+pub struct WebSocketMessage {
+    #[serde(rename(deserialize = "messageType"))]
+    pub message_type: String,
+    #[serde(default)]
+    pub data: HashMap<String, Value>,
+}
+
+impl WebSocketMessage {
+    /// Whether the message notifies property changes. Others (actions,
+    /// events…) are not relevant for the aggregator.
+    pub fn is_property_status(&self) -> bool {
+        self.message_type == "propertyStatus"
+    }
+}
+
 // https://webthings.io/schemas/#capabilities
 #[derive(Debug, Deserialize)]
 pub enum Capability {
@@ -54,7 +107,7 @@ pub enum Capability {
 }
 
 // https://webthings.io/schemas/#properties
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum PropertyType {
     #[serde(rename(deserialize = "AlarmProperty"))]
     Alarm,
@@ -118,4 +171,46 @@ pub enum PropertyType {
     // Non-standard.
     #[serde(rename(deserialize = "RecurrenceProperty"))]
     Recurrence,
+
+    // Any other type, kept as is.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl PropertyType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Alarm => "AlarmProperty",
+            Self::BarometricPressure => "BarometricPressureProperty",
+            Self::Boolean => "BooleanProperty",
+            Self::Brightness => "BrightnessProperty",
+            Self::ColorMode => "ColorModeProperty",
+            Self::Color => "ColorProperty",
+            Self::ColorTemperature => "ColorTemperatureProperty",
+            Self::Concentration => "ConcentrationProperty",
+            Self::Current => "CurrentProperty",
+            Self::Density => "DensityProperty",
+            Self::Frequency => "FrequencyProperty",
+            Self::HeatingCooling => "HeatingCoolingProperty",
+            Self::Humidity => "HumidityProperty",
+            Self::Image => "ImageProperty",
+            Self::InstantaneousPowerFactor => "InstantaneousPowerFactorProperty",
+            Self::InstantaneousPower => "InstantaneousPowerProperty",
+            Self::Leak => "LeakProperty",
+            Self::Level => "LevelProperty",
+            Self::Locked => "LockedProperty",
+            Self::Motion => "MotionProperty",
+            Self::OnOff => "OnOffProperty",
+            Self::Open => "OpenProperty",
+            Self::Pushed => "PushedProperty",
+            Self::Smoke => "SmokeProperty",
+            Self::TargetTemperature => "TargetTemperatureProperty",
+            Self::Temperature => "TemperatureProperty",
+            Self::ThermostatMode => "ThermostatModeProperty",
+            Self::Video => "VideoProperty",
+            Self::Voltage => "VoltageProperty",
+            Self::Recurrence => "RecurrenceProperty",
+            Self::Other(other) => other,
+        }
+    }
 }
@@ -1,4 +1,22 @@
 use crate::thing::generic;
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("thing with ID `{0}` cannot be identified")]
+    Unknown(String),
+
+    #[error("{0}")]
+    Malformed(&'static str),
+}
+
+impl From<&'static str> for Error {
+    fn from(reason: &'static str) -> Self {
+        Self::Malformed(reason)
+    }
+}
 
 #[derive(Debug)]
 pub enum Thing {
@@ -10,10 +28,14 @@ pub enum Thing {
     HousePower(HousePower),
     DomesticHotWater(DomesticHotWater),
     Air(Air),
+    ChargingStation(ChargingStation),
+    Vehicle(Vehicle),
+    CurrentWeather(CurrentWeather),
+    Forecast(Forecast),
 }
 
 impl TryFrom<&generic::Thing> for Thing {
-    type Error = String;
+    type Error = Error;
 
     fn try_from(generic: &generic::Thing) -> Result<Self, Self::Error> {
         macro_rules! property {
@@ -52,6 +74,15 @@ impl TryFrom<&generic::Thing> for Thing {
                         )
                     })?
             };
+
+            // A property that older versions of a thing may not have.
+            (optional $thing_name:ident . $name:ident from $generic_thing:ident $as_ty:ident) => {
+                $generic_thing
+                    .properties
+                    .get(stringify!($name))
+                    .and_then(|property| property.value.as_ref())
+                    .and_then(|value| value.$as_ty())
+            };
         }
 
         Ok(match generic.id.as_str() {
@@ -60,6 +91,9 @@ impl TryFrom<&generic::Thing> for Thing {
                 temperature: property!(Battery.temperature from generic as_f64),
                 state_of_charge: property!(Battery.state_of_charge from generic as_f64),
                 voltage: property!(Battery.voltage from generic as_f64),
+                state: property!(optional Battery.state from generic as_str)
+                    .map(ToString::to_string),
+                health: property!(optional Battery.health from generic as_f64),
             }),
 
             "urn:dev:ops:pv-inverter-0" => Thing::PvInverterAll(PvInverter {
@@ -111,9 +145,55 @@ impl TryFrom<&generic::Thing> for Thing {
                 extracted_temperature: property!(Air.extracted_air from generic as_f64),
                 discharged_temperature: property!(Air.discharged_air from generic as_f64),
                 wanted_temperature: property!(Air.wanted_air_inside from generic as_f64),
+                inside_co2_level: property!(optional Air.inside_co2_level from generic as_f64),
+                supplied_air_fan_speed: property!(optional Air.supplied_air_fan_speed from generic as_f64),
+                extracted_air_fan_speed: property!(optional Air.extracted_air_fan_speed from generic as_f64),
+            }),
+
+            "urn:dev:ops:car-charging-station" => Thing::ChargingStation(ChargingStation {
+                is_available: property!(ChargingStation.socket_availability from generic as_bool),
+                is_charging: property!(ChargingStation.socket_charging from generic as_bool),
+                number_of_phases: property!(ChargingStation.socket_number_of_phases from generic as_u64),
+                power: property!(ChargingStation.socket_power from generic as_f64),
+                applied_max_current: property!(ChargingStation.socket_current from generic as_f64),
+                max_current: property!(ChargingStation.max_current from generic as_f64),
+                delivered_energy: property!(ChargingStation.socket_delivered_energy from generic as_f64),
+                station_temperature: property!(ChargingStation.station_temperature from generic as_f64),
+            }),
+
+            "urn:dev:ops:vehicle" => Thing::Vehicle(Vehicle {
+                state_of_charge: property!(Vehicle.state_of_charge from generic as_f64),
+                is_charging: property!(Vehicle.is_charging from generic as_bool),
+                remaining_range: property!(Vehicle.remaining_range from generic as_f64),
+                odometer: property!(Vehicle.odometer from generic as_f64),
             }),
 
-            id => return Err(format!("Thing with ID `{}` cannot be identified", id)),
+            "urn:dev:ops:current_weather" => Thing::CurrentWeather(CurrentWeather {
+                clouds: property!(CurrentWeather.clouds from generic as_f64),
+                temperature: property!(CurrentWeather.temperature from generic as_f64),
+                apparent_temperature: property!(CurrentWeather.apparent_temperature from generic as_f64),
+                humidity: property!(CurrentWeather.humidity from generic as_f64),
+                dew_point: property!(CurrentWeather.dew_point from generic as_f64),
+                pressure: property!(CurrentWeather.pressure from generic as_f64),
+                uv_index: property!(CurrentWeather.uv_index from generic as_f64),
+                visibility: property!(CurrentWeather.visibility from generic as_f64),
+                wind_degree: property!(CurrentWeather.wind_degree from generic as_f64),
+                wind_speed: property!(CurrentWeather.wind_speed from generic as_f64),
+                wind_gust: property!(CurrentWeather.wind_gust from generic as_f64),
+                rain: property!(CurrentWeather.rain from generic as_f64),
+                snow: property!(CurrentWeather.snow from generic as_f64),
+                condition: property!(CurrentWeather.condition from generic as_u64),
+            }),
+
+            "urn:dev:ops:forecast" => Thing::Forecast(Forecast {
+                hourly: property!(Forecast.hourly from generic as_array)
+                    .iter()
+                    .map(|weather| serde_json::from_value(weather.clone()))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| "Property `hourly` of the `Thing::Forecast` is malformed")?,
+            }),
+
+            id => return Err(Error::Unknown(id.to_string())),
         })
     }
 }
@@ -124,6 +204,8 @@ pub struct Battery {
     pub temperature: f64,
     pub state_of_charge: f64,
     pub voltage: f64,
+    pub state: Option<String>,
+    pub health: Option<f64>,
 }
 
 #[derive(Debug)]
@@ -158,4 +240,81 @@ pub struct Air {
     pub extracted_temperature: f64,
     pub discharged_temperature: f64,
     pub wanted_temperature: f64,
+    pub inside_co2_level: Option<f64>,
+    pub supplied_air_fan_speed: Option<f64>,
+    pub extracted_air_fan_speed: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct ChargingStation {
+    pub is_available: bool,
+    pub is_charging: bool,
+    pub number_of_phases: u64,
+    pub power: f64,
+    pub applied_max_current: f64,
+    pub max_current: f64,
+    pub delivered_energy: f64,
+    pub station_temperature: f64,
+}
+
+#[derive(Debug)]
+pub struct Vehicle {
+    pub state_of_charge: f64,
+    pub is_charging: bool,
+    pub remaining_range: f64,
+    pub odometer: f64,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct CurrentWeather {
+    pub clouds: f64,
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub dew_point: f64,
+    pub pressure: f64,
+    pub uv_index: f64,
+    pub visibility: f64,
+    pub wind_degree: f64,
+    pub wind_speed: f64,
+    pub wind_gust: f64,
+    pub rain: f64,
+    pub snow: f64,
+    pub condition: u64,
+}
+
+#[derive(Debug)]
+pub struct Forecast {
+    pub hourly: Vec<HourlyWeather>,
+}
+
+/// A forecast for one hour, as serialized by the `weather` program.
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct HourlyWeather {
+    #[serde(with = "ts_seconds")]
+    pub datetime: DateTime<Utc>,
+    pub clouds: f64,
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub dew_point: f64,
+    pub pressure: f64,
+    pub uv_index: f64,
+    pub visibility: Option<f64>,
+    pub wind_degree: f64,
+    pub wind_speed: f64,
+    pub wind_gust: Option<f64>,
+    pub rain: Option<Precipitation>,
+    pub snow: Option<Precipitation>,
+    pub conditions: Vec<WeatherCondition>,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Precipitation {
+    pub one_hour: f64,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct WeatherCondition {
+    pub id: u64,
 }
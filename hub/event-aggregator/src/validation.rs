@@ -0,0 +1,388 @@
+use crate::{configuration::ValidationRule, thing::generic};
+use chrono::{DateTime, Utc};
+use hub_database::models;
+use std::collections::{BTreeMap, HashMap};
+
+/// A numeric property value, at a given time.
+type Sample = (DateTime<Utc>, f64);
+
+/// The last samples of a property.
+#[derive(Default)]
+struct History {
+    accepted: Option<Sample>,
+    /// The last sample, if it has been rejected because of the previous
+    /// ones (a change too fast, or an unexpected `0`).
+    rejected: Option<Sample>,
+}
+
+/// Validate the numeric property values of the things against rules,
+/// before they are stored, to drop the glitches of the sources, e.g. a
+/// state of charge of 0 % for one sample.
+///
+/// A change that is confirmed by the next sample is accepted: if the
+/// value has really changed, it is rejected once, not forever.
+pub struct Validator {
+    /// Rules indexed by thing ID, then by property name.
+    rules: BTreeMap<String, BTreeMap<String, ValidationRule>>,
+    /// History of the validated properties, indexed by thing ID and
+    /// property name.
+    histories: HashMap<(String, String), History>,
+}
+
+impl Validator {
+    pub fn new(rules: BTreeMap<String, BTreeMap<String, ValidationRule>>) -> Self {
+        Self {
+            rules,
+            histories: HashMap::new(),
+        }
+    }
+
+    /// Remove the rejected values from the thing, and return them with
+    /// the reasons of their rejections.
+    pub fn validate(&mut self, thing: &mut generic::Thing) -> Vec<models::RejectedSample> {
+        let rules = match self.rules.get(&thing.id) {
+            Some(rules) => rules,
+            None => return Vec::new(),
+        };
+
+        let mut rejected_samples = Vec::new();
+
+        for (property_name, rule) in rules {
+            let property = match thing.properties.get_mut(property_name) {
+                Some(property) => property,
+                None => continue,
+            };
+
+            let value = match property.value.as_ref().and_then(|value| value.as_f64()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let history = self
+                .histories
+                .entry((thing.id.clone(), property_name.clone()))
+                .or_default();
+
+            if let Err(reason) = check(rule, history, (thing.fetched_at, value)) {
+                eprintln!(
+                    "Rejecting property `{}` of thing `{}`: {}",
+                    property_name, thing.id, reason
+                );
+
+                rejected_samples.push(models::RejectedSample {
+                    time: thing.fetched_at,
+                    thing_id: thing.id.clone(),
+                    property_name: property_name.clone(),
+                    value: property.value.take().unwrap_or_default(),
+                    reason,
+                });
+            }
+        }
+
+        rejected_samples
+    }
+}
+
+/// Check a sample against a rule, and update the history of its
+/// property.
+fn check(rule: &ValidationRule, history: &mut History, sample: Sample) -> Result<(), String> {
+    let (time, value) = sample;
+
+    if let Some(minimum) = rule.minimum.filter(|minimum| value < *minimum) {
+        return Err(format!("`{}` is below the minimum `{}`", value, minimum));
+    }
+
+    if let Some(maximum) = rule.maximum.filter(|maximum| value > *maximum) {
+        return Err(format!("`{}` is above the maximum `{}`", value, maximum));
+    }
+
+    let change_per_second = |(from_time, from_value): Sample| {
+        let elapsed = (time - from_time).num_milliseconds() as f64 / 1000.;
+
+        if elapsed > 0. {
+            (value - from_value).abs() / elapsed
+        } else {
+            0.
+        }
+    };
+
+    // A later sample close to the last rejected one confirms it. The
+    // rejected sample itself, sent again, does not.
+    let is_confirmed = history.rejected.is_some_and(|rejected| {
+        time > rejected.0
+            && match rule.maximum_change_per_second {
+                Some(maximum) => change_per_second(rejected) <= maximum,
+                None => rejected.1 == value,
+            }
+    });
+
+    if let (false, Some(accepted)) = (is_confirmed, history.accepted) {
+        if rule.reject_zero_after_non_zero && value == 0. && accepted.1 != 0. {
+            history.rejected = Some(sample);
+
+            return Err(format!("`0` after `{}`", accepted.1));
+        }
+
+        if let Some(maximum) = rule
+            .maximum_change_per_second
+            .filter(|maximum| change_per_second(accepted) > *maximum)
+        {
+            history.rejected = Some(sample);
+
+            return Err(format!(
+                "`{}` after `{}` changes by more than `{}` per second",
+                value, accepted.1, maximum
+            ));
+        }
+    }
+
+    history.accepted = Some(sample);
+    history.rejected = None;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).unwrap()
+    }
+
+    /// Check the samples in order, and return whether each one is
+    /// accepted.
+    fn check_all(rule: &ValidationRule, samples: &[Sample]) -> Vec<bool> {
+        let mut history = History::default();
+
+        samples
+            .iter()
+            .map(|sample| check(rule, &mut history, *sample).is_ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_minimum_and_maximum() {
+        let rule = ValidationRule {
+            minimum: Some(0.),
+            maximum: Some(100.),
+            ..ValidationRule::default()
+        };
+
+        assert_eq!(
+            check_all(
+                &rule,
+                &[
+                    (at(0), 0.),
+                    (at(1), 100.),
+                    (at(2), -1.),
+                    (at(3), 100.5),
+                    (at(4), 50.)
+                ]
+            ),
+            [true, true, false, false, true]
+        );
+
+        // Bounds are never confirmed.
+        assert_eq!(
+            check_all(&rule, &[(at(0), 150.), (at(1), 150.), (at(2), 150.)]),
+            [false, false, false]
+        );
+
+        let mut history = History::default();
+
+        assert_eq!(
+            check(&rule, &mut history, (at(0), -3.)),
+            Err("`-3` is below the minimum `0`".to_string())
+        );
+        assert!(history.accepted.is_none());
+    }
+
+    #[test]
+    fn test_reject_zero_after_non_zero() {
+        let rule = ValidationRule {
+            reject_zero_after_non_zero: true,
+            ..ValidationRule::default()
+        };
+
+        // A single `0` is a glitch.
+        assert_eq!(
+            check_all(&rule, &[(at(0), 80.), (at(1), 0.), (at(2), 80.)]),
+            [true, false, true]
+        );
+
+        // A first `0` is accepted, and so are the next ones.
+        assert_eq!(
+            check_all(&rule, &[(at(0), 0.), (at(1), 0.), (at(2), 5.)]),
+            [true, true, true]
+        );
+
+        let mut history = History::default();
+        check(&rule, &mut history, (at(0), 80.)).unwrap();
+
+        assert_eq!(
+            check(&rule, &mut history, (at(1), 0.)),
+            Err("`0` after `80`".to_string())
+        );
+        assert_eq!(history.accepted, Some((at(0), 80.)));
+        assert_eq!(history.rejected, Some((at(1), 0.)));
+    }
+
+    #[test]
+    fn test_zero_confirmed_after_rejection() {
+        let rule = ValidationRule {
+            reject_zero_after_non_zero: true,
+            ..ValidationRule::default()
+        };
+
+        // The value has really dropped to `0`: it's rejected once, then
+        // accepted when the next sample confirms it.
+        assert_eq!(
+            check_all(
+                &rule,
+                &[(at(0), 80.), (at(1), 0.), (at(2), 0.), (at(3), 0.)]
+            ),
+            [true, false, true, true]
+        );
+    }
+
+    #[test]
+    fn test_maximum_change_per_second() {
+        let rule = ValidationRule {
+            maximum_change_per_second: Some(1.),
+            ..ValidationRule::default()
+        };
+
+        assert_eq!(
+            check_all(
+                &rule,
+                &[
+                    (at(0), 50.),
+                    (at(10), 60.),
+                    // +30 in 1 s.
+                    (at(11), 90.),
+                    // Back to normal, compared to the last accepted one.
+                    (at(12), 61.),
+                ]
+            ),
+            [true, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_change_confirmed_after_rejection() {
+        let rule = ValidationRule {
+            maximum_change_per_second: Some(1.),
+            ..ValidationRule::default()
+        };
+
+        // The value has really jumped: the next sample is close to the
+        // rejected one, and is accepted…
+        assert_eq!(
+            check_all(
+                &rule,
+                &[(at(0), 50.), (at(1), 90.), (at(2), 90.5), (at(3), 91.)]
+            ),
+            [true, false, true, true]
+        );
+
+        // … but not if it's far from it too.
+        assert_eq!(
+            check_all(&rule, &[(at(0), 50.), (at(1), 90.), (at(2), 10.)]),
+            [true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_same_rejected_sample_does_not_confirm_it() {
+        let zero = ValidationRule {
+            reject_zero_after_non_zero: true,
+            ..ValidationRule::default()
+        };
+
+        assert_eq!(
+            check_all(
+                &zero,
+                &[(at(0), 80.), (at(1), 0.), (at(1), 0.), (at(2), 0.)]
+            ),
+            [true, false, false, true]
+        );
+
+        let change = ValidationRule {
+            maximum_change_per_second: Some(1.),
+            ..ValidationRule::default()
+        };
+
+        assert_eq!(
+            check_all(
+                &change,
+                &[(at(0), 50.), (at(1), 90.), (at(1), 90.), (at(2), 90.)]
+            ),
+            [true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let rules = [(
+            "urn:dev:ops:battery".to_string(),
+            [
+                (
+                    "state_of_charge".to_string(),
+                    ValidationRule {
+                        minimum: Some(0.),
+                        maximum: Some(100.),
+                        reject_zero_after_non_zero: true,
+                        ..ValidationRule::default()
+                    },
+                ),
+                (
+                    "state".to_string(),
+                    ValidationRule {
+                        minimum: Some(0.),
+                        ..ValidationRule::default()
+                    },
+                ),
+            ]
+            .into(),
+        )]
+        .into();
+
+        let mut validator = Validator::new(rules);
+
+        let thing = |id: &str, state_of_charge: f64| -> generic::Thing {
+            serde_json::from_value(json!({
+                "id": id,
+                "title": "Battery",
+                "properties": {
+                    "state_of_charge": { "title": "State of charge", "value": state_of_charge },
+                    "state": { "title": "State", "value": "idle" },
+                },
+            }))
+            .unwrap()
+        };
+
+        let mut battery = thing("urn:dev:ops:battery", 80.);
+        assert!(validator.validate(&mut battery).is_empty());
+
+        let mut battery = thing("urn:dev:ops:battery", 0.);
+        let rejected_samples = validator.validate(&mut battery);
+
+        assert_eq!(rejected_samples.len(), 1);
+        assert_eq!(rejected_samples[0].thing_id, "urn:dev:ops:battery");
+        assert_eq!(rejected_samples[0].property_name, "state_of_charge");
+        assert_eq!(rejected_samples[0].value, json!(0.));
+        assert_eq!(rejected_samples[0].time, battery.fetched_at);
+        assert!(battery.properties["state_of_charge"].value.is_none());
+
+        // Non-numeric values are not validated.
+        assert_eq!(battery.properties["state"].value, Some(json!("idle")));
+
+        // Things without rules are not validated.
+        let mut other = thing("urn:dev:ops:other", -1.);
+        assert!(validator.validate(&mut other).is_empty());
+        assert_eq!(other.properties["state_of_charge"].value, Some(json!(-1.)));
+    }
+}
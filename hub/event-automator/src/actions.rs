@@ -1,45 +1,379 @@
-use reqwest::{blocking::Client, Method};
+use crate::configuration::Action;
+use reqwest::{
+    blocking::{Client, Response},
+    Method, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
 
-#[derive(Debug)]
+/// How often the status of a requested action is polled.
+const ACTION_STATUS_POLLING_RATE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
 pub(crate) enum Error {
-    UnableToSendWebThingRequest,
-}
-
-fn http_json(method: Method, url: &str, json_payload: &'static str) -> Result<(), Error> {
-    let client = Client::new();
-
-    match client
-        .request(method, url)
-        .header("Content-Type", "application/json")
-        .body(json_payload)
-        .send()
-    {
-        Ok(response) if response.status().is_success() => Ok(()),
-        _ => Err(Error::UnableToSendWebThingRequest),
-    }
-}
-
-pub(crate) fn close_blinds(blinds_url: &str) -> Result<(), Error> {
-    let json_payload = "{\"close\": {}}";
-
-    // Louise.
-    http_json(
-        Method::POST,
-        &format!("{}/4/actions/close", blinds_url),
-        json_payload,
-    )?;
-    // Éli.
-    http_json(
-        Method::POST,
-        &format!("{}/3/actions/close", blinds_url),
-        json_payload,
-    )?;
-    // Parents.
-    http_json(
-        Method::POST,
-        &format!("{}/2/actions/close", blinds_url),
-        json_payload,
-    )?;
-
-    Ok(())
+    #[error("failed to send `{0} {1}`: {2}")]
+    Request(Method, String, reqwest::Error),
+
+    #[error("`{0} {1}` has failed with `{2}`: {3}")]
+    Status(Method, String, StatusCode, String),
+
+    #[error("invalid response to `{0} {1}`: {2}")]
+    InvalidResponse(Method, String, String),
+
+    #[error("thing `{0}` is not found on {1}")]
+    UnknownThing(String, String),
+
+    #[error("thing `{0}` is ambiguous, it is found on {1}; set the `server` of the action, or use the thing ID")]
+    AmbiguousThing(String, String),
+
+    #[error("thing `{0}` has no action `{1}`")]
+    UnknownAction(String, String),
+
+    #[error("thing `{0}` has no property `{1}`")]
+    UnknownProperty(String, String),
+
+    #[error("property `{1}` of thing `{0}` is read-only")]
+    ReadOnlyProperty(String, String),
+
+    #[error("action `{1}` of thing `{0}` has ended with the status `{2}`")]
+    ActionFailed(String, String, String),
+
+    #[error("action `{1}` of thing `{0}` is still `{2}` after {3:?}")]
+    ActionTimeout(String, String, String, Duration),
+}
+
+/// The description of a thing, as given by a WebThing server.
+#[derive(Deserialize, Debug)]
+struct Description {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    href: String,
+    #[serde(default)]
+    properties: HashMap<String, Interaction>,
+    #[serde(default)]
+    actions: HashMap<String, Interaction>,
+}
+
+/// A property or an action of a thing.
+#[derive(Deserialize, Debug)]
+struct Interaction {
+    #[serde(default, rename = "readOnly")]
+    read_only: bool,
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+impl Interaction {
+    /// The link with the relation `rel`, or `default`.
+    fn href(&self, rel: &str, default: String) -> String {
+        self.links
+            .iter()
+            .find(|link| link.rel.as_deref() == Some(rel))
+            .map_or(default, |link| link.href.clone())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Link {
+    #[serde(default)]
+    rel: Option<String>,
+    href: String,
+}
+
+/// A client of the WebThing servers, that runs the actions of the
+/// rules on any of their things. Things are found by ID, by title, or
+/// by `href` (e.g. `4` for `/4`), on all the servers, or only on the
+/// `server` of the action.
+pub(crate) struct WebThings {
+    client: Client,
+    servers: Vec<SocketAddr>,
+    action_timeout: Duration,
+    /// The descriptions of the things, with their servers. They are
+    /// fetched again when a thing is not found.
+    things: Vec<(SocketAddr, Description)>,
+}
+
+impl WebThings {
+    pub(crate) fn new(servers: Vec<SocketAddr>, action_timeout: Duration) -> Self {
+        Self {
+            client: Client::new(),
+            servers,
+            action_timeout,
+            things: Vec::new(),
+        }
+    }
+
+    pub(crate) fn run(&mut self, action: &Action) -> Result<(), Error> {
+        match action {
+            Action::Invoke {
+                server,
+                thing,
+                action,
+                input,
+            } => self.invoke(server.as_ref(), thing, action, input.as_ref()),
+
+            Action::Set {
+                server,
+                thing,
+                property,
+                value,
+            } => self.set(server.as_ref(), thing, property, value),
+        }
+    }
+
+    /// Request an action, and wait until it is completed.
+    pub(crate) fn invoke(
+        &mut self,
+        server: Option<&SocketAddr>,
+        thing: &str,
+        action: &str,
+        input: Option<&Value>,
+    ) -> Result<(), Error> {
+        let (server, description) = self.find(server, thing)?;
+        let href = description
+            .actions
+            .get(action)
+            .ok_or_else(|| Error::UnknownAction(description.id.clone(), action.to_string()))?
+            .href("action", format!("{}/actions/{}", description.href, action));
+        let thing = description.id.clone();
+
+        let body = match input {
+            Some(input) => json!({ action: { "input": input } }),
+            None => json!({ action: {} }),
+        };
+
+        let mut status = self.request_action(Method::POST, server, &href, action, Some(&body))?;
+        let started_at = Instant::now();
+
+        loop {
+            match status.status.as_str() {
+                "completed" => return Ok(()),
+                "created" | "pending" => (),
+                _ => {
+                    return Err(Error::ActionFailed(
+                        thing,
+                        action.to_string(),
+                        status.status,
+                    ))
+                }
+            }
+
+            // Without `href`, the status cannot be polled.
+            let href = match status.href {
+                Some(href) => href,
+                None => return Ok(()),
+            };
+
+            if started_at.elapsed() >= self.action_timeout {
+                return Err(Error::ActionTimeout(
+                    thing,
+                    action.to_string(),
+                    status.status,
+                    self.action_timeout,
+                ));
+            }
+
+            thread::sleep(ACTION_STATUS_POLLING_RATE);
+
+            status = self.request_action(Method::GET, server, &href, action, None)?;
+        }
+    }
+
+    /// Set a property, if it is writable.
+    pub(crate) fn set(
+        &mut self,
+        server: Option<&SocketAddr>,
+        thing: &str,
+        property: &str,
+        value: &Value,
+    ) -> Result<(), Error> {
+        let (server, description) = self.find(server, thing)?;
+        let interaction = description
+            .properties
+            .get(property)
+            .ok_or_else(|| Error::UnknownProperty(description.id.clone(), property.to_string()))?;
+
+        if interaction.read_only {
+            return Err(Error::ReadOnlyProperty(
+                description.id.clone(),
+                property.to_string(),
+            ));
+        }
+
+        let href = interaction.href(
+            "property",
+            format!("{}/properties/{}", description.href, property),
+        );
+
+        self.request(
+            Method::PUT,
+            server,
+            &href,
+            Some(&json!({ property: value })),
+        )?;
+
+        Ok(())
+    }
+
+    /// Find a thing by ID, title, or `href`.
+    fn find(
+        &mut self,
+        server: Option<&SocketAddr>,
+        thing: &str,
+    ) -> Result<(SocketAddr, &Description), Error> {
+        let is_candidate = |(address, description): &(SocketAddr, Description)| {
+            server.is_none_or(|server| server == address)
+                && (description.id == thing
+                    || description.title == thing
+                    || description.href.trim_start_matches('/') == thing)
+        };
+
+        if !self.things.iter().any(is_candidate) {
+            self.fetch_descriptions(server);
+        }
+
+        let mut candidates = self.things.iter().filter(|thing| is_candidate(thing));
+
+        match (candidates.next(), candidates.next()) {
+            (Some((address, description)), None) => Ok((*address, description)),
+
+            (Some(first), Some(second)) => Err(Error::AmbiguousThing(
+                thing.to_string(),
+                [first, second]
+                    .into_iter()
+                    .chain(candidates)
+                    .map(|(address, description)| format!("`{}` as `{}`", address, description.id))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )),
+
+            (None, _) => Err(Error::UnknownThing(
+                thing.to_string(),
+                match server {
+                    Some(server) => format!("`{}`", server),
+                    None => self
+                        .servers
+                        .iter()
+                        .map(|server| format!("`{}`", server))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                },
+            )),
+        }
+    }
+
+    /// Fetch the descriptions of the things of the servers, or only of
+    /// `server`. A server that fails keeps its previous descriptions.
+    fn fetch_descriptions(&mut self, server: Option<&SocketAddr>) {
+        let servers = match server {
+            Some(server) => vec![*server],
+            None => self.servers.clone(),
+        };
+
+        for server in servers {
+            let descriptions = match self.request(Method::GET, server, "/", None) {
+                // A server has a thing, or an array of things.
+                Ok(Value::Array(descriptions)) => descriptions
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<Vec<Description>, _>>(),
+                Ok(description) => serde_json::from_value(description).map(|d| vec![d]),
+                Err(error) => {
+                    eprintln!("Failed to fetch the things of `{}`: {}", server, error);
+
+                    continue;
+                }
+            };
+
+            match descriptions {
+                Ok(descriptions) => {
+                    self.things.retain(|(address, _)| *address != server);
+                    self.things.extend(
+                        descriptions
+                            .into_iter()
+                            .map(|description| (server, description)),
+                    );
+                }
+
+                Err(error) => eprintln!("Invalid things of `{}`: {}", server, error),
+            }
+        }
+    }
+
+    /// Request or poll an action, and return its status.
+    fn request_action(
+        &self,
+        method: Method,
+        server: SocketAddr,
+        href: &str,
+        action: &str,
+        body: Option<&Value>,
+    ) -> Result<ActionStatus, Error> {
+        let response = self.request(method.clone(), server, href, body)?;
+
+        // The status is `{"<action>": {"href": …, "status": …}}`.
+        response
+            .get(action)
+            .cloned()
+            .and_then(|status| serde_json::from_value(status).ok())
+            .ok_or_else(|| {
+                Error::InvalidResponse(
+                    method,
+                    url(server, href),
+                    format!("expected the status of the action `{}`", action),
+                )
+            })
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        server: SocketAddr,
+        href: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Error> {
+        let url = url(server, href);
+        let mut request = self.client.request(method.clone(), &url);
+
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response: Response = request
+            .send()
+            .map_err(|error| Error::Request(method.clone(), url.clone(), error))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+
+        if !status.is_success() {
+            return Err(Error::Status(method, url, status, text));
+        }
+
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        serde_json::from_str(&text)
+            .map_err(|error| Error::InvalidResponse(method, url, error.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct ActionStatus {
+    status: String,
+    #[serde(default)]
+    href: Option<String>,
+}
+
+fn url(server: SocketAddr, href: &str) -> String {
+    format!("http://{}/{}", server, href.trim_start_matches('/'))
 }
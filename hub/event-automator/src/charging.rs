@@ -0,0 +1,365 @@
+use crate::configuration::ChargingConfiguration;
+use alfen::{reader, state::State, writer};
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{Double, Nullable},
+};
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio_modbus::prelude::*;
+
+/// A car does not charge below this current, in ampere.
+const MINIMUM_CURRENT: f64 = 6.;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to talk to the charging station: {0}")]
+    Modbus(#[from] io::Error),
+}
+
+/// The averaged measurements, if there are recent ones.
+#[derive(QueryableByName, Default)]
+struct Measurements {
+    /// PV power, in watt.
+    #[sql_type = "Nullable<Double>"]
+    production: Option<f64>,
+    /// House power, in watt.
+    #[sql_type = "Nullable<Double>"]
+    consumption: Option<f64>,
+    /// Battery state of charge, in percent.
+    #[sql_type = "Nullable<Double>"]
+    state_of_charge: Option<f64>,
+}
+
+/// Charge the car with the PV surplus only: the surplus is computed
+/// from the PV power, the house power, and the battery state of charge
+/// in the database, and the charging current of the Alfen station is
+/// adjusted to it, in a thread.
+pub struct Controller {
+    database_connection: PgConnection,
+    regulator: Regulator,
+    /// The last written current, in ampere.
+    current: Option<u16>,
+}
+
+/// Compute the charging current from the measurements.
+///
+/// Charging resumes when the surplus is `hysteresis` above the minimum
+/// current, and pauses when it is below it. These switches happen at
+/// most once per `minimum_state_duration`, except for a pause because
+/// the battery is below its minimum state of charge, or because the
+/// measurements are missing, which happens at once.
+struct Regulator {
+    configuration: ChargingConfiguration,
+    is_charging: bool,
+    /// When charging has resumed or paused for the last time.
+    changed_at: Option<Instant>,
+}
+
+impl Controller {
+    pub fn new(configuration: ChargingConfiguration, database_connection: PgConnection) -> Self {
+        Self {
+            database_connection,
+            regulator: Regulator {
+                configuration,
+                is_charging: false,
+                changed_at: None,
+            },
+            current: None,
+        }
+    }
+
+    pub fn spawn(mut self) {
+        println!(
+            "Charging the car with the PV surplus, on `{}`",
+            self.regulator.configuration.alfen_address
+        );
+
+        thread::spawn(move || loop {
+            if let Err(error) = self.adjust() {
+                eprintln!("Failed to adjust the charging current: {}", error);
+            }
+
+            thread::sleep(Duration::from_secs(self.regulator.configuration.interval));
+        });
+    }
+
+    fn adjust(&mut self) -> Result<(), Error> {
+        let measurements = self.measure().unwrap_or_else(|error| {
+            eprintln!("Failed to read the measurements: {}", error);
+
+            Measurements::default()
+        });
+
+        let mut context = client::sync::tcp::connect(self.regulator.configuration.alfen_address)?;
+        let state = reader::read(&mut context)?;
+        let current = self.regulator.current_for(&measurements, &state);
+
+        writer::set_socket_current(&mut context, &state, current)?;
+
+        if self.current != Some(current) {
+            println!("Charging current set to {} A", current);
+
+            self.current = Some(current);
+        }
+
+        Ok(())
+    }
+
+    fn measure(&self) -> QueryResult<Measurements> {
+        sql_query(
+            "SELECT \
+                 (SELECT AVG(power) FROM electricity_production WHERE time > now() - make_interval(secs => $1)) AS production, \
+                 (SELECT AVG(house_power) FROM electricity_consumption WHERE time > now() - make_interval(secs => $1)) AS consumption, \
+                 (SELECT AVG(state_of_charge) FROM electricity_storage WHERE time > now() - make_interval(secs => $1)) AS state_of_charge",
+        )
+        .bind::<Double, _>(self.regulator.configuration.averaging_period as f64)
+        .get_result(&self.database_connection)
+    }
+}
+
+impl Regulator {
+    /// The charging current, in ampere, `0` to pause.
+    fn current_for(&mut self, measurements: &Measurements, state: &State) -> u16 {
+        let configuration = &self.configuration;
+        let number_of_phases = f64::from(state.socket.number_of_phases.as_u8());
+        // The station refuses a current that is not below its maximum.
+        let maximum_current = f64::from(configuration.maximum_current)
+            .min(f64::from(state.station_status.max_current.0).ceil() - 1.);
+
+        let (production, consumption, state_of_charge) = match (
+            measurements.production,
+            measurements.consumption,
+            measurements.state_of_charge,
+        ) {
+            (Some(production), Some(consumption), Some(state_of_charge)) => {
+                (production, consumption, state_of_charge)
+            }
+            _ => return self.pause("there is no recent measurement"),
+        };
+
+        if state_of_charge < configuration.minimum_state_of_charge {
+            return self.pause(&format!(
+                "the battery is at {:.0} %, below {:.0} %",
+                state_of_charge, configuration.minimum_state_of_charge
+            ));
+        }
+
+        if number_of_phases == 0. {
+            return self.pause("the number of phases of the station is unknown");
+        }
+
+        if maximum_current < MINIMUM_CURRENT {
+            return self.pause(&format!(
+                "the maximum current, {} A, is below {} A",
+                maximum_current, MINIMUM_CURRENT
+            ));
+        }
+
+        let surplus = production - consumption
+            + if configuration.house_power_includes_charging {
+                f64::from(state.socket.power.0)
+            } else {
+                0.
+            };
+        let available_current = surplus / (configuration.voltage * number_of_phases);
+
+        let should_charge = if self.is_charging {
+            available_current >= MINIMUM_CURRENT
+        } else {
+            available_current >= MINIMUM_CURRENT + configuration.hysteresis
+        };
+
+        let can_change = self.changed_at.is_none_or(|changed_at| {
+            changed_at.elapsed() >= Duration::from_secs(configuration.minimum_state_duration)
+        });
+
+        if should_charge != self.is_charging && can_change {
+            println!(
+                "{} charging the car: the surplus is {:.0} W, i.e. {:.1} A",
+                if should_charge { "Resuming" } else { "Pausing" },
+                surplus,
+                available_current
+            );
+
+            self.is_charging = should_charge;
+            self.changed_at = Some(Instant::now());
+        }
+
+        if self.is_charging {
+            available_current.clamp(MINIMUM_CURRENT, maximum_current) as u16
+        } else {
+            0
+        }
+    }
+
+    fn pause(&mut self, reason: &str) -> u16 {
+        if self.is_charging {
+            println!("Pausing charging the car: {}", reason);
+
+            self.is_charging = false;
+            self.changed_at = Some(Instant::now());
+        }
+
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alfen::{
+        state::PhaseNumber,
+        unit::{Amp, Watt},
+    };
+
+    fn regulator(minimum_state_duration: u64) -> Regulator {
+        Regulator {
+            configuration: serde_json::from_value(serde_json::json!({
+                "alfen_address": "127.0.0.1:502",
+                "minimum_state_duration": minimum_state_duration,
+                "house_power_includes_charging": false,
+            }))
+            .unwrap(),
+            is_charging: false,
+            changed_at: None,
+        }
+    }
+
+    /// A three-phase station, which accepts up to 32 A.
+    fn state() -> State {
+        let mut state = State::default();
+        state.station_status.max_current = Amp(32.);
+        state.socket.number_of_phases = PhaseNumber::Three;
+
+        state
+    }
+
+    /// The measurements for a surplus, in watt, i.e. 690 W per ampere
+    /// on three phases of 230 V.
+    fn surplus(surplus: f64) -> Measurements {
+        Measurements {
+            production: Some(surplus + 500.),
+            consumption: Some(500.),
+            state_of_charge: Some(90.),
+        }
+    }
+
+    #[test]
+    fn test_pause_and_resume_with_hysteresis() {
+        let mut regulator = regulator(0);
+        let state = state();
+
+        // 6.5 A: not enough to resume, the hysteresis is 1 A.
+        assert_eq!(regulator.current_for(&surplus(4485.), &state), 0);
+        // 7.2 A.
+        assert_eq!(regulator.current_for(&surplus(4968.), &state), 7);
+        // 6.5 A: enough to keep charging.
+        assert_eq!(regulator.current_for(&surplus(4485.), &state), 6);
+        // 5.8 A.
+        assert_eq!(regulator.current_for(&surplus(4002.), &state), 0);
+        // 6.5 A.
+        assert_eq!(regulator.current_for(&surplus(4485.), &state), 0);
+        // 7 A, exactly.
+        assert_eq!(regulator.current_for(&surplus(4830.), &state), 7);
+    }
+
+    #[test]
+    fn test_no_flapping_within_the_minimum_state_duration() {
+        let mut regulator = regulator(300);
+        let state = state();
+
+        assert_eq!(regulator.current_for(&surplus(4968.), &state), 7);
+
+        // 5.8 A, but charging has resumed just now: charge at the
+        // minimum current.
+        assert_eq!(regulator.current_for(&surplus(4002.), &state), 6);
+        assert!(regulator.is_charging);
+
+        regulator.changed_at = Some(Instant::now() - Duration::from_secs(300));
+        assert_eq!(regulator.current_for(&surplus(4002.), &state), 0);
+
+        // 7.2 A, but charging has paused just now.
+        assert_eq!(regulator.current_for(&surplus(4968.), &state), 0);
+        assert!(!regulator.is_charging);
+
+        regulator.changed_at = Some(Instant::now() - Duration::from_secs(300));
+        assert_eq!(regulator.current_for(&surplus(4968.), &state), 7);
+    }
+
+    #[test]
+    fn test_immediate_pause() {
+        let mut regulator = regulator(300);
+        let state = state();
+
+        assert_eq!(regulator.current_for(&surplus(6900.), &state), 10);
+
+        // The battery is below its minimum state of charge.
+        let measurements = Measurements {
+            state_of_charge: Some(79.),
+            ..surplus(6900.)
+        };
+        assert_eq!(regulator.current_for(&measurements, &state), 0);
+        assert!(!regulator.is_charging);
+
+        regulator.changed_at = None;
+        assert_eq!(regulator.current_for(&surplus(6900.), &state), 10);
+
+        // The measurements are missing.
+        assert_eq!(regulator.current_for(&Measurements::default(), &state), 0);
+        assert!(!regulator.is_charging);
+
+        regulator.changed_at = None;
+        assert_eq!(regulator.current_for(&surplus(6900.), &state), 10);
+
+        let measurements = Measurements {
+            production: None,
+            ..surplus(6900.)
+        };
+        assert_eq!(regulator.current_for(&measurements, &state), 0);
+        assert!(!regulator.is_charging);
+    }
+
+    #[test]
+    fn test_current_is_clamped() {
+        let mut regulator = regulator(0);
+        let mut state = state();
+
+        // 29 A, above the configured maximum.
+        assert_eq!(regulator.current_for(&surplus(20010.), &state), 16);
+
+        // The station refuses a current that is not below its maximum.
+        state.station_status.max_current = Amp(10.);
+        assert_eq!(regulator.current_for(&surplus(20010.), &state), 9);
+
+        state.station_status.max_current = Amp(10.5);
+        assert_eq!(regulator.current_for(&surplus(20010.), &state), 10);
+
+        // The station cannot deliver the minimum current.
+        state.station_status.max_current = Amp(6.);
+        assert_eq!(regulator.current_for(&surplus(20010.), &state), 0);
+
+        // One phase: 87 A.
+        state.station_status.max_current = Amp(32.);
+        state.socket.number_of_phases = PhaseNumber::One;
+        assert_eq!(regulator.current_for(&surplus(20010.), &state), 16);
+
+        // 6.5 A, on one phase.
+        assert_eq!(regulator.current_for(&surplus(1495.), &state), 6);
+    }
+
+    #[test]
+    fn test_house_power_includes_charging() {
+        let mut regulator = regulator(0);
+        regulator.configuration.house_power_includes_charging = true;
+        let mut state = state();
+
+        // The car draws 10 A, included in the house power.
+        state.socket.power = Watt(6900.);
+        assert_eq!(regulator.current_for(&surplus(0.), &state), 10);
+    }
+}
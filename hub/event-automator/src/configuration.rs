@@ -1,5 +1,13 @@
+use crate::{
+    events::Event,
+    schedule::Cron,
+    state::SunPeriod,
+    sun::{SunCoordinate, SunEvent},
+};
+use chrono::{NaiveDateTime, NaiveTime};
 use directories_next::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
     net,
     path::{Path, PathBuf},
@@ -9,6 +17,268 @@ use std::{
 pub struct Configuration {
     pub database_url: String,
     pub blinds_url: net::SocketAddr,
+    /// WebThing servers where the things of the actions are looked up,
+    /// in addition to `blinds_url`.
+    #[serde(default)]
+    pub webthings: Vec<net::SocketAddr>,
+    /// Seconds to wait for an action to complete.
+    #[serde(default = "default_action_timeout")]
+    pub action_timeout: u64,
+    /// Where the house is, for the position of the sun.
+    #[serde(default)]
+    pub location: Location,
+    /// Charge the car with the PV surplus only, if set.
+    #[serde(default)]
+    pub charging: Option<ChargingConfiguration>,
+    #[serde(default = "default_rules")]
+    pub rules: Vec<Rule>,
+}
+
+/// An automation: when the trigger fires, and all the conditions are
+/// met, the actions are run, in order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    /// What to do with the schedules missed while the automator was
+    /// stopped.
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Trigger {
+    /// An event fired by the automator, e.g. `sun-period-change`.
+    Event { event: Event },
+
+    /// A local time of the day, e.g. `22:30`.
+    Time { time: NaiveTime },
+
+    /// A cron expression, in local time, e.g. `0 7 * * mon-fri`.
+    Cron { cron: Cron },
+
+    /// A local date and time, once, e.g. `2026-12-24T18:00:00`.
+    At { at: NaiveDateTime },
+
+    /// An event of the sun, shifted by `offset` minutes, e.g. 20
+    /// minutes after the sunset.
+    Sun {
+        event: SunEvent,
+        #[serde(default)]
+        offset: i64,
+    },
+
+    /// A value crossing a threshold: it fires when the value becomes
+    /// above or below it, not while it stays so.
+    Threshold(Threshold),
+}
+
+/// What to do with the schedules (`time`, `sun`, `cron` and `at`
+/// triggers) that were missed while the automator was stopped. The
+/// conditions are checked when catching up, not when it was missed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CatchUp {
+    /// Forget them.
+    #[default]
+    Skip,
+
+    /// Run the rule once, if any was missed.
+    Latest,
+
+    /// Run the rule once per missed schedule.
+    All,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Condition {
+    /// The sun is in this period.
+    SunPeriod { period: SunPeriod },
+
+    /// The local time is in this range; it can span midnight, e.g.
+    /// from `22:00` to `07:00`.
+    Between { from: NaiveTime, to: NaiveTime },
+
+    /// The latest value is above or below the threshold.
+    Value(Threshold),
+}
+
+/// A value compared to a threshold. When both `above` and `below` are
+/// set, the value must be in between.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Threshold {
+    #[serde(flatten)]
+    pub source: Source,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f64>,
+}
+
+/// Where a value is read from: the latest row in the database, or the
+/// sun.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum Source {
+    /// A numeric column of a table, e.g. `state_of_charge` of
+    /// `battery`.
+    Column { table: String, column: String },
+
+    /// A numeric property of a thing, as stored in `thing_property` by
+    /// the aggregator in generic mode.
+    Property { thing: String, property: String },
+
+    /// A coordinate of the sun, now, in degrees; it is not read from
+    /// the database.
+    Sun { sun: SunCoordinate },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            latitude: 46.78657339107215,
+            longitude: 6.806581635522576,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Action {
+    /// Request an action of a thing, and wait until it is completed.
+    Invoke {
+        /// The WebThing server of the thing, if its ID, title or `href`
+        /// is not unique.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        server: Option<net::SocketAddr>,
+        thing: String,
+        action: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        input: Option<Value>,
+    },
+
+    /// Set a writable property of a thing.
+    Set {
+        /// The WebThing server of the thing, if its ID, title or `href`
+        /// is not unique.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        server: Option<net::SocketAddr>,
+        thing: String,
+        property: String,
+        value: Value,
+    },
+}
+
+/// The PV-surplus charging of the car, on the Alfen charging station.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChargingConfiguration {
+    /// The Modbus address of the charging station.
+    pub alfen_address: net::SocketAddr,
+    /// Seconds between two adjustments of the charging current. The
+    /// current is written every time, as the station falls back to its
+    /// safe current when it is not refreshed.
+    #[serde(default = "default_charging_interval")]
+    pub interval: u64,
+    /// Seconds over which the powers are averaged, to smooth the
+    /// passing clouds.
+    #[serde(default = "default_averaging_period")]
+    pub averaging_period: u64,
+    /// The car is not charged when the state of charge of the battery
+    /// is below this, in percent.
+    #[serde(default = "default_minimum_state_of_charge")]
+    pub minimum_state_of_charge: f64,
+    /// In ampere.
+    #[serde(default = "default_maximum_current")]
+    pub maximum_current: u16,
+    /// Amperes of surplus above the minimum charging current (6 A)
+    /// required to resume charging.
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis: f64,
+    /// Seconds during which charging is not paused after it has
+    /// resumed, and not resumed after it has been paused.
+    #[serde(default = "default_minimum_state_duration")]
+    pub minimum_state_duration: u64,
+    /// The voltage of a phase.
+    #[serde(default = "default_voltage")]
+    pub voltage: f64,
+    /// Whether the house power includes the power of the charging
+    /// station.
+    #[serde(default = "default_house_power_includes_charging")]
+    pub house_power_includes_charging: bool,
+}
+
+fn default_charging_interval() -> u64 {
+    30
+}
+
+fn default_averaging_period() -> u64 {
+    120
+}
+
+fn default_minimum_state_of_charge() -> f64 {
+    80.
+}
+
+fn default_maximum_current() -> u16 {
+    16
+}
+
+fn default_hysteresis() -> f64 {
+    1.
+}
+
+fn default_minimum_state_duration() -> u64 {
+    5 * 60
+}
+
+fn default_voltage() -> f64 {
+    230.
+}
+
+fn default_house_power_includes_charging() -> bool {
+    true
+}
+
+fn default_action_timeout() -> u64 {
+    60
+}
+
+/// Close the bedrooms' blinds when the night comes.
+fn default_rules() -> Vec<Rule> {
+    let close = |thing: &str| Action::Invoke {
+        server: None,
+        thing: thing.to_string(),
+        action: "close".to_string(),
+        input: None,
+    };
+
+    vec![Rule {
+        name: "Close the bedrooms' blinds at night".to_string(),
+        catch_up: CatchUp::Skip,
+        trigger: Trigger::Event {
+            event: Event::SunPeriodChange,
+        },
+        conditions: vec![Condition::SunPeriod {
+            period: SunPeriod::Night,
+        }],
+        // Louise, Éli, and parents.
+        actions: vec![
+            close("urn:dev:ops:blind-4"),
+            close("urn:dev:ops:blind-3"),
+            close("urn:dev:ops:blind-2"),
+        ],
+    }]
 }
 
 impl Default for Configuration {
@@ -19,6 +289,11 @@ impl Default for Configuration {
                 net::Ipv4Addr::new(127, 0, 0, 1),
                 1234,
             )),
+            webthings: Vec::new(),
+            action_timeout: default_action_timeout(),
+            location: Location::default(),
+            charging: None,
+            rules: default_rules(),
         }
     }
 }
@@ -38,6 +313,15 @@ pub fn get_path() -> Result<PathBuf, &'static str> {
     Ok(path)
 }
 
+/// The file where the time of the last evaluation of the rules is
+/// kept, to catch up with the missed schedules.
+pub fn get_state_path() -> Result<PathBuf, &'static str> {
+    let project = ProjectDirs::from("rs", "", "hub-event-automator")
+        .ok_or("Failed to find the configuration project directory.")?;
+
+    Ok(project.data_dir().join("last-evaluation"))
+}
+
 pub fn load(path: impl AsRef<Path>) -> Result<Configuration, confy::ConfyError> {
     confy::load_path(path)
 }
@@ -1,16 +1,27 @@
-use crate::actions;
-use crate::events::Event;
-use crate::state::{Context, State, SunPeriod, UpdateState};
+use crate::actions::WebThings;
+use crate::configuration::Location;
+use crate::rules::Rules;
+use crate::state::{Context, State, UpdateState};
+use chrono::prelude::*;
 use diesel::pg::PgConnection;
-use std::{net, thread, time::Duration};
-
-pub fn run(database_connection: PgConnection, blinds_url: &net::SocketAddr) {
+use std::{thread, time::Duration};
+
+/// The state is updated, and the rules are evaluated, at least this
+/// often, and earlier when a rule is scheduled before.
+const INTERVAL: Duration = Duration::from_secs(60 * 2);
+
+pub(crate) fn run(
+    database_connection: PgConnection,
+    location: Location,
+    mut webthings: WebThings,
+    mut rules: Rules,
+) {
     let mut new_events = Vec::new();
     let mut state = State::default();
 
-    let blinds_url = format!("http://{}", blinds_url);
     let state_context = Context {
         database_connection,
+        location,
     };
 
     let loupe = thread::spawn(move || loop {
@@ -18,24 +29,24 @@ pub fn run(database_connection: PgConnection, blinds_url: &net::SocketAddr) {
 
         state = state.update(&state_context, &mut new_events);
 
-        dbg!(&state);
-        dbg!(&new_events);
+        for rule in rules.evaluate(&state_context, &state, &new_events) {
+            println!("Running rule `{}`", rule.name);
 
-        for new_event in &new_events {
-            match new_event {
-                Event::SunPeriodChange if state.sun.period == SunPeriod::Night => {
-                    actions::close_blinds(&blinds_url).unwrap()
-                }
+            for action in &rule.actions {
+                if let Err(error) = webthings.run(action) {
+                    eprintln!("Rule `{}` has failed: {}", rule.name, error);
 
-                _ => {
-                    // do nothing.
+                    break;
                 }
             }
         }
 
-        println!("Sleeping…");
+        let until = Local::now() + chrono::Duration::from_std(INTERVAL).unwrap();
+        let wake_up_at = rules.next_schedule(&state_context, until).unwrap_or(until);
+
+        println!("Sleeping until {}…", wake_up_at);
 
-        thread::sleep(Duration::from_secs(60 * 2));
+        thread::sleep((wake_up_at - Local::now()).to_std().unwrap_or_default());
     });
 
     loupe
@@ -1,5 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 /// The events this program can fire.
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
 pub enum Event {
     /// The sun has either risen or set.
     SunPeriodChange,
@@ -1,17 +1,21 @@
+#[macro_use]
+extern crate diesel;
+
 mod actions;
+mod charging;
 mod command;
 mod configuration;
-mod database;
 mod event_loop;
 mod events;
+mod rules;
+mod schedule;
 mod state;
+mod sun;
 
-#[macro_use]
-extern crate diesel;
-
-use crate::command::Options;
+use crate::{actions::WebThings, charging::Controller, command::Options, rules::Rules};
 use diesel::prelude::*;
 use human_panic::setup_panic;
+use std::time::Duration;
 use structopt::StructOpt;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -47,13 +51,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let blinds_url = options.blinds_url.unwrap_or(configuration.blinds_url);
+    let webthings = WebThings::new(
+        std::iter::once(blinds_url)
+            .chain(
+                configuration
+                    .webthings
+                    .into_iter()
+                    .filter(|address| *address != blinds_url),
+            )
+            .collect(),
+        Duration::from_secs(configuration.action_timeout),
+    );
+    let rules = Rules::new(configuration.rules, configuration::get_state_path()?)?;
 
     let database_connection = PgConnection::establish(&database_url).expect(&format!(
         "Failed to connect to database at `{}`",
         &database_url
     ));
 
-    event_loop::run(database_connection, &blinds_url);
+    if let Some(charging) = configuration.charging {
+        let database_connection = PgConnection::establish(&database_url)?;
+
+        Controller::new(charging, database_connection).spawn();
+    }
+
+    event_loop::run(
+        database_connection,
+        configuration.location,
+        webthings,
+        rules,
+    );
 
     Ok(())
 }
@@ -0,0 +1,482 @@
+use crate::{
+    configuration::{CatchUp, Condition, Location, Rule, Source, Threshold, Trigger},
+    events::Event,
+    schedule,
+    state::{Context, State},
+    sun,
+};
+use chrono::{prelude::*, Duration};
+use diesel::{
+    prelude::*,
+    sql_query,
+    sql_types::{Double, Nullable, Text},
+};
+use std::{collections::HashMap, fs, iter, path::PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("rule `{0}`: `{1}` is not a valid table or column name")]
+    InvalidIdentifier(String, String),
+
+    #[error("rule `{0}`: a threshold needs `above`, `below`, or both")]
+    EmptyThreshold(String),
+}
+
+#[derive(QueryableByName)]
+struct Latest {
+    #[sql_type = "Nullable<Double>"]
+    value: Option<f64>,
+}
+
+/// The rules of the configuration, evaluated by the event loop.
+pub struct Rules {
+    rules: Vec<Rule>,
+    /// Whether the threshold of the trigger was crossed at the previous
+    /// evaluation, indexed by rule.
+    crossed_thresholds: HashMap<usize, bool>,
+    previous_evaluation: DateTime<Local>,
+    /// Whether the previous evaluation happened before the automator
+    /// was stopped, i.e. whether schedules may have been missed.
+    is_catching_up: bool,
+    /// Where the time of the previous evaluation is kept.
+    state_path: PathBuf,
+}
+
+impl Rules {
+    pub fn new(rules: Vec<Rule>, state_path: PathBuf) -> Result<Self, Error> {
+        for rule in &rules {
+            let thresholds = rule
+                .conditions
+                .iter()
+                .filter_map(|condition| match condition {
+                    Condition::Value(threshold) => Some(threshold),
+                    _ => None,
+                })
+                .chain(match &rule.trigger {
+                    Trigger::Threshold(threshold) => Some(threshold),
+                    _ => None,
+                });
+
+            for threshold in thresholds {
+                if threshold.above.is_none() && threshold.below.is_none() {
+                    return Err(Error::EmptyThreshold(rule.name.clone()));
+                }
+
+                // Table and column names are written in the queries
+                // as is.
+                if let Source::Column { table, column } = &threshold.source {
+                    for identifier in [table, column] {
+                        if identifier.is_empty()
+                            || !identifier
+                                .chars()
+                                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                        {
+                            return Err(Error::InvalidIdentifier(
+                                rule.name.clone(),
+                                identifier.clone(),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let now = Local::now();
+        let previous_evaluation = fs::read_to_string(&state_path)
+            .ok()
+            .and_then(|time| match DateTime::parse_from_rfc3339(time.trim()) {
+                Ok(time) => Some(time.with_timezone(&Local)),
+                Err(error) => {
+                    eprintln!(
+                        "Invalid time of the last evaluation in `{}`: {}",
+                        state_path.display(),
+                        error
+                    );
+
+                    None
+                }
+            })
+            .filter(|time| *time < now);
+
+        Ok(Self {
+            rules,
+            crossed_thresholds: HashMap::new(),
+            previous_evaluation: previous_evaluation.unwrap_or(now),
+            is_catching_up: previous_evaluation.is_some(),
+            state_path,
+        })
+    }
+
+    /// Return the rules that fire since the previous evaluation, and
+    /// whose conditions are met. A rule is returned as many times as it
+    /// must run, when catching up with the missed schedules.
+    pub fn evaluate(
+        &mut self,
+        context: &Context,
+        state: &State,
+        new_events: &[Event],
+    ) -> Vec<&Rule> {
+        let now = Local::now();
+        let previous_evaluation = self.previous_evaluation;
+        let missed_since = self.is_catching_up.then_some(previous_evaluation);
+        let mut values = Values::new(context);
+        let mut fired_rules = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            let number_of_runs = match &rule.trigger {
+                Trigger::Event { event } => new_events.contains(event) as usize,
+
+                Trigger::Threshold(threshold) => {
+                    // The first value only tells whether the threshold
+                    // is already crossed.
+                    values.compare(threshold).is_some_and(|is_crossed| {
+                        matches!(
+                            self.crossed_thresholds.insert(index, is_crossed),
+                            Some(false)
+                        ) && is_crossed
+                    }) as usize
+                }
+
+                trigger => runs(
+                    rule,
+                    missed_since,
+                    schedules(trigger, &context.location, previous_evaluation, now).len(),
+                ),
+            };
+
+            if number_of_runs > 0
+                && rule.conditions.iter().all(|condition| match condition {
+                    Condition::SunPeriod { period } => state.sun.period == *period,
+
+                    Condition::Between { from, to } => {
+                        let time = now.time();
+
+                        if from <= to {
+                            *from <= time && time < *to
+                        } else {
+                            *from <= time || time < *to
+                        }
+                    }
+
+                    Condition::Value(threshold) => values.compare(threshold).unwrap_or(false),
+                })
+            {
+                fired_rules.extend(iter::repeat_n(rule, number_of_runs));
+            }
+        }
+
+        self.previous_evaluation = now;
+        self.is_catching_up = false;
+
+        if let Err(error) = self
+            .state_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&self.state_path, now.to_rfc3339()))
+        {
+            eprintln!(
+                "Failed to write the time of the last evaluation in `{}`: {}",
+                self.state_path.display(),
+                error
+            );
+        }
+
+        fired_rules
+    }
+
+    /// The first schedule of the rules after the previous evaluation,
+    /// until `until`, so that the event loop wakes up on time.
+    pub fn next_schedule(
+        &self,
+        context: &Context,
+        until: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                schedules(
+                    &rule.trigger,
+                    &context.location,
+                    self.previous_evaluation,
+                    until,
+                )
+                .into_iter()
+                .next()
+            })
+            .min()
+    }
+}
+
+/// The moments when a trigger fires in `(from, to]`, in order. Events
+/// and thresholds are not scheduled: they have none.
+fn schedules(
+    trigger: &Trigger,
+    location: &Location,
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+) -> Vec<DateTime<Local>> {
+    match trigger {
+        Trigger::Event { .. } | Trigger::Threshold(_) => Vec::new(),
+
+        Trigger::Time { time } => daily(from, to, |date| {
+            schedule::resolve(&Local, date.and_time(*time))
+        }),
+
+        Trigger::Sun { event, offset } => daily(from, to, |date| {
+            event
+                .time(location, date, &Local)
+                .map(|time| time.with_timezone(&Local) + Duration::minutes(*offset))
+        }),
+
+        Trigger::Cron { cron } => cron.occurrences(from, to),
+
+        Trigger::At { at } => schedule::resolve(&Local, *at)
+            .filter(|at| from < *at && *at <= to)
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// How many times a rule must run for its schedules in the evaluated
+/// period. `missed_since` is set if they have been missed while the
+/// automator was stopped.
+fn runs(rule: &Rule, missed_since: Option<DateTime<Local>>, schedules: usize) -> usize {
+    let since = match missed_since {
+        Some(since) => since,
+        None => return schedules.min(1),
+    };
+
+    let runs = match rule.catch_up {
+        CatchUp::Skip => 0,
+        CatchUp::Latest => schedules.min(1),
+        CatchUp::All => schedules,
+    };
+
+    if schedules > 0 {
+        println!(
+            "Rule `{}` has missed {} schedule(s) since {}, running it {} time(s)",
+            rule.name, schedules, since, runs
+        );
+    }
+
+    runs
+}
+
+/// The moments of a daily moment in `(from, to]`, in order. The
+/// moments of the previous and the next days are considered too, as an
+/// offset can shift them to another day.
+fn daily<F>(from: DateTime<Local>, to: DateTime<Local>, moment_of: F) -> Vec<DateTime<Local>>
+where
+    F: Fn(NaiveDate) -> Option<DateTime<Local>>,
+{
+    let mut date = from.date_naive() - Duration::days(1);
+    let mut moments = Vec::new();
+
+    while date <= to.date_naive() + Duration::days(1) {
+        if let Some(moment) = moment_of(date).filter(|moment| from < *moment && *moment <= to) {
+            moments.push(moment);
+        }
+
+        date += Duration::days(1);
+    }
+
+    moments.sort();
+
+    moments
+}
+
+/// The latest values of the sources, read once per evaluation.
+struct Values<'a> {
+    context: &'a Context,
+    values: HashMap<Source, Option<f64>>,
+}
+
+impl<'a> Values<'a> {
+    fn new(context: &'a Context) -> Self {
+        Self {
+            context,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Whether the latest value of the source is within the threshold,
+    /// if there is a value.
+    fn compare(&mut self, threshold: &Threshold) -> Option<bool> {
+        let context = self.context;
+        let value = *self
+            .values
+            .entry(threshold.source.clone())
+            .or_insert_with(|| match read(context, &threshold.source) {
+                Ok(value) => value,
+                Err(error) => {
+                    eprintln!("Failed to read {:?}: {}", threshold.source, error);
+
+                    None
+                }
+            });
+
+        value.map(|value| {
+            threshold.above.is_none_or(|above| value > above)
+                && threshold.below.is_none_or(|below| value < below)
+        })
+    }
+}
+
+fn read(context: &Context, source: &Source) -> QueryResult<Option<f64>> {
+    let latest = match source {
+        Source::Column { table, column } => sql_query(format!(
+            "SELECT {}::DOUBLE PRECISION AS value FROM {} ORDER BY time DESC LIMIT 1",
+            column, table
+        ))
+        .load::<Latest>(&context.database_connection)?,
+
+        Source::Property { thing, property } => sql_query(
+            "SELECT numeric_value AS value FROM thing_property \
+             WHERE thing_id = $1 AND property_name = $2 \
+             ORDER BY time DESC LIMIT 1",
+        )
+        .bind::<Text, _>(thing)
+        .bind::<Text, _>(property)
+        .load::<Latest>(&context.database_connection)?,
+
+        Source::Sun { sun } => {
+            return Ok(Some(sun::position(&context.location, Utc::now()).get(*sun)))
+        }
+    };
+
+    Ok(latest.into_iter().next().and_then(|latest| latest.value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(catch_up: CatchUp) -> Rule {
+        Rule {
+            name: "test".to_string(),
+            catch_up,
+            trigger: Trigger::Event {
+                event: Event::SunPeriodChange,
+            },
+            conditions: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_runs_without_missed_schedules() {
+        for catch_up in [CatchUp::Skip, CatchUp::Latest, CatchUp::All] {
+            assert_eq!(runs(&rule(catch_up), None, 0), 0);
+            assert_eq!(runs(&rule(catch_up), None, 1), 1);
+
+            // Schedules between two evaluations run once.
+            assert_eq!(runs(&rule(catch_up), None, 3), 1);
+        }
+    }
+
+    #[test]
+    fn test_runs_with_missed_schedules() {
+        let since = Some(Local::now() - Duration::days(2));
+
+        assert_eq!(runs(&rule(CatchUp::Skip), since, 3), 0);
+        assert_eq!(runs(&rule(CatchUp::Latest), since, 3), 1);
+        assert_eq!(runs(&rule(CatchUp::All), since, 3), 3);
+
+        for catch_up in [CatchUp::Skip, CatchUp::Latest, CatchUp::All] {
+            assert_eq!(runs(&rule(catch_up), since, 0), 0);
+        }
+    }
+
+    #[test]
+    fn test_schedules() {
+        let location = Location::default();
+        let from = Local::now();
+        let to = from + Duration::hours(1);
+
+        let every_15_minutes = schedules(
+            &Trigger::Cron {
+                cron: "*/15 * * * *".to_string().try_into().unwrap(),
+            },
+            &location,
+            from,
+            to,
+        );
+
+        assert_eq!(every_15_minutes.len(), 4);
+        assert!(every_15_minutes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(every_15_minutes[0] <= from + Duration::minutes(15));
+
+        let at = (from + Duration::minutes(10))
+            .naive_local()
+            .with_nanosecond(0)
+            .unwrap();
+
+        assert_eq!(
+            schedules(&Trigger::At { at }, &location, from, to),
+            [schedule::resolve(&Local, at).unwrap()]
+        );
+        assert!(schedules(&Trigger::At { at }, &location, to, to + Duration::hours(1)).is_empty());
+
+        // Events are not scheduled.
+        assert!(schedules(
+            &Trigger::Event {
+                event: Event::SunPeriodChange
+            },
+            &location,
+            from,
+            to
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_daily() {
+        let moment_at = |hour| {
+            move |date: NaiveDate| {
+                Local
+                    .from_local_datetime(&date.and_hms_opt(hour, 0, 0)?)
+                    .earliest()
+            }
+        };
+        let at = |date: &str, hour| {
+            Local
+                .from_local_datetime(
+                    &date
+                        .parse::<NaiveDate>()
+                        .unwrap()
+                        .and_hms_opt(hour, 0, 0)
+                        .unwrap(),
+                )
+                .earliest()
+                .unwrap()
+        };
+
+        // Missed for three days.
+        assert_eq!(
+            daily(at("2026-06-01", 12), at("2026-06-04", 12), moment_at(7)),
+            [
+                at("2026-06-02", 7),
+                at("2026-06-03", 7),
+                at("2026-06-04", 7)
+            ]
+        );
+        assert_eq!(
+            daily(at("2026-06-01", 12), at("2026-06-04", 12), moment_at(12)),
+            [
+                at("2026-06-02", 12),
+                at("2026-06-03", 12),
+                at("2026-06-04", 12)
+            ]
+        );
+        assert!(daily(at("2026-06-01", 12), at("2026-06-01", 13), moment_at(7)).is_empty());
+
+        // A moment shifted to the next day is counted.
+        let next_day = |date: NaiveDate| Some(at(&date.to_string(), 23) + Duration::hours(2));
+
+        assert_eq!(
+            daily(at("2026-06-02", 0), at("2026-06-02", 2), next_day),
+            [at("2026-06-02", 1)]
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use chrono::{prelude::*, Duration, LocalResult};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, ops::RangeInclusive};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron expression, in local time: `minute hour day-of-month month
+/// day-of-week`, e.g. `0 7 * * mon-fri`.
+///
+/// Fields are `*`, values, ranges (`1-5`), lists (`1,15`), and steps
+/// (`*/15`, `8-18/2`). Months and days of the week can be named, and
+/// `0` and `7` are both Sunday. When both the day of the month and the
+/// day of the week are restricted, a day matching either is
+/// scheduled, as with cron. `@yearly`, `@monthly`, `@weekly`, `@daily`
+/// and `@hourly` are accepted too.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// The scheduled moments in `(from, to]`, in the time zone of
+    /// `from`.
+    pub fn occurrences<Tz: TimeZone>(
+        &self,
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    ) -> Vec<DateTime<Tz>> {
+        let time_zone = from.timezone();
+        let mut occurrences = Vec::new();
+        // A moment skipped by a DST change is shifted to the next day
+        // at most.
+        let mut date = from.date_naive() - Duration::days(1);
+
+        while date <= to.date_naive() {
+            if self.matches_date(date) {
+                for hour in (0..24).filter(|hour| has(self.hours, *hour)) {
+                    for minute in (0..60).filter(|minute| has(self.minutes, *minute)) {
+                        if let Some(moment) = date
+                            .and_hms_opt(hour, minute, 0)
+                            .and_then(|local| resolve(&time_zone, local))
+                            .filter(|moment| from < *moment && *moment <= to)
+                        {
+                            occurrences.push(moment);
+                        }
+                    }
+                }
+            }
+
+            date += Duration::days(1);
+        }
+
+        // Shifted moments can collide with scheduled ones.
+        occurrences.sort();
+        occurrences.dedup();
+
+        occurrences
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+
+        has(self.months, date.month())
+            && match (self.any_day, self.any_weekday) {
+                (false, false) => day || weekday,
+                _ => day && weekday,
+            }
+    }
+}
+
+impl TryFrom<String> for Cron {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        let fields = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            fields => fields,
+        }
+        .split_whitespace()
+        .collect::<Vec<_>>();
+
+        if fields.len() != 5 {
+            return Err(format!(
+                "the cron expression `{}` must have 5 fields: minute, hour, day of month, month, and day of week",
+                expression
+            ));
+        }
+
+        let field = |nth: usize, name: &str, range, names: &[&str], offset| {
+            parse_field(fields[nth], range, names, offset).map_err(|reason| {
+                format!(
+                    "invalid {} `{}` in the cron expression `{}`: {}",
+                    name, fields[nth], expression, reason
+                )
+            })
+        };
+
+        let minutes = field(0, "minute", 0..=59, &[], 0)?;
+        let hours = field(1, "hour", 0..=23, &[], 0)?;
+        let days = field(2, "day of month", 1..=31, &[], 0)?;
+        let months = field(3, "month", 1..=12, &MONTHS, 1)?;
+        let mut weekdays = field(4, "day of week", 0..=7, &WEEKDAYS, 0)?;
+
+        // Sunday is `0` or `7`.
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+            expression,
+        })
+    }
+}
+
+impl From<Cron> for String {
+    fn from(cron: Cron) -> Self {
+        cron.expression
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.expression)
+    }
+}
+
+/// Parse a cron field into a set of values, as bits. `names` are the
+/// names of the values, starting at `offset`.
+fn parse_field(
+    field: &str,
+    range: RangeInclusive<u32>,
+    names: &[&str],
+    offset: u32,
+) -> Result<u64, String> {
+    let value = |value: &str| -> Result<u32, String> {
+        let value = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(value))
+        {
+            Some(index) => index as u32 + offset,
+            None => value
+                .parse()
+                .map_err(|_| format!("`{}` is not a value", value))?,
+        };
+
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(format!(
+                "`{}` is not between `{}` and `{}`",
+                value,
+                range.start(),
+                range.end()
+            ))
+        }
+    };
+
+    let mut values = 0;
+
+    for part in field.split(',') {
+        let (interval, step) = match part.split_once('/') {
+            Some((interval, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (interval, Some(step)),
+                _ => return Err(format!("`{}` is not a step", step)),
+            },
+            None => (part, None),
+        };
+
+        let (first, last) = match interval.split_once('-') {
+            _ if interval == "*" => (*range.start(), *range.end()),
+            Some((first, last)) => (value(first)?, value(last)?),
+            // `5/10` is `5-<end>/10`.
+            None if step.is_some() => (value(interval)?, *range.end()),
+            None => (value(interval)?, value(interval)?),
+        };
+
+        if first > last {
+            return Err(format!("`{}` is an empty range", interval));
+        }
+
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            values |= 1 << value;
+        }
+    }
+
+    Ok(values)
+}
+
+fn has(values: u64, value: u32) -> bool {
+    values & (1 << value) != 0
+}
+
+/// The moment of a date and time in `time_zone`, across DST changes: a
+/// repeated time is its first occurrence, and a skipped time is shifted
+/// by the length of the gap, e.g. 02:30 becomes 03:30 when clocks go
+/// forward at 02:00.
+pub fn resolve<Tz: TimeZone>(time_zone: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    match time_zone.from_local_datetime(&local) {
+        LocalResult::Single(moment) => Some(moment),
+        // The moments are not always in chronological order, and, at
+        // the end of the overlap, one of them may not have this local
+        // time.
+        LocalResult::Ambiguous(first, second) => [first, second]
+            .into_iter()
+            .filter(|moment| moment.with_timezone(time_zone).naive_local() == local)
+            .min(),
+        LocalResult::None => {
+            // The offset before the gap.
+            let offset = time_zone
+                .from_local_datetime(&(local - Duration::days(1)))
+                .earliest()?
+                .offset()
+                .fix();
+
+            offset
+                .from_local_datetime(&local)
+                .single()
+                .map(|moment| moment.with_timezone(time_zone))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{Europe::Zurich, Tz};
+
+    /// Schedules are in local time, and DST changes are tested in
+    /// Zurich: clocks go forward at 02:00 on 2026-03-29, and back at
+    /// 03:00 on 2026-10-25.
+    fn local(date_time: &str) -> DateTime<Tz> {
+        resolve(&Zurich, date_time.parse().unwrap()).unwrap()
+    }
+
+    fn utc(date_time: &str) -> DateTime<Utc> {
+        date_time.parse().unwrap()
+    }
+
+    fn cron(expression: &str) -> Cron {
+        Cron::try_from(expression.to_string()).unwrap()
+    }
+
+    fn values(bits: u64) -> Vec<u32> {
+        (0..64).filter(|value| has(bits, *value)).collect()
+    }
+
+    fn occurrences(expression: &str, from: &str, to: &str) -> Vec<DateTime<Utc>> {
+        cron(expression)
+            .occurrences(local(from), local(to))
+            .into_iter()
+            .map(|moment| moment.with_timezone(&Utc))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_field() {
+        let field = |field| parse_field(field, 0..=59, &[], 0).map(values);
+
+        assert_eq!(field("5"), Ok(vec![5]));
+        assert_eq!(field("*"), Ok((0..=59).collect()));
+        assert_eq!(field("1-5"), Ok(vec![1, 2, 3, 4, 5]));
+        assert_eq!(field("1,15,30"), Ok(vec![1, 15, 30]));
+        assert_eq!(field("*/15"), Ok(vec![0, 15, 30, 45]));
+        assert_eq!(field("8-18/4"), Ok(vec![8, 12, 16]));
+        assert_eq!(field("50/5"), Ok(vec![50, 55]));
+        assert_eq!(field("1-3,10-20/5,59"), Ok(vec![1, 2, 3, 10, 15, 20, 59]));
+    }
+
+    #[test]
+    fn test_parse_named_field() {
+        assert_eq!(
+            parse_field("mon-fri", 0..=7, &WEEKDAYS, 0).map(values),
+            Ok(vec![1, 2, 3, 4, 5])
+        );
+        assert_eq!(
+            parse_field("JAN,jun-aug", 1..=12, &MONTHS, 1).map(values),
+            Ok(vec![1, 6, 7, 8])
+        );
+
+        // Sunday is `0` or `7`.
+        assert_eq!(values(cron("0 0 * * 7").weekdays), [0, 7]);
+        assert_eq!(values(cron("0 0 * * sun").weekdays), [0]);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in [
+            "",
+            "0 7 * *",
+            "0 7 * * * *",
+            "60 7 * * *",
+            "0 24 * * *",
+            "0 7 0 * *",
+            "0 7 * 13 *",
+            "0 7 * * 8",
+            "0 7 5-1 * *",
+            "*/0 7 * * *",
+            "*/x 7 * * *",
+            "0 7 * foo *",
+            "0 7 * * mon-",
+            "@weekdays",
+        ] {
+            assert!(
+                Cron::try_from(expression.to_string()).is_err(),
+                "`{}` must be invalid",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_macros() {
+        assert_eq!(cron("@daily").expression, "@daily");
+        assert_eq!(
+            occurrences("@hourly", "2026-06-01T10:00:00", "2026-06-01T12:30:00"),
+            [utc("2026-06-01T09:00:00Z"), utc("2026-06-01T10:00:00Z")]
+        );
+        assert_eq!(
+            occurrences("@weekly", "2026-06-01T00:00:00", "2026-06-14T00:00:00"),
+            [utc("2026-06-06T22:00:00Z"), utc("2026-06-13T22:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_are_in_the_half_open_interval() {
+        assert_eq!(
+            occurrences("0 7 * * *", "2026-06-01T07:00:00", "2026-06-02T07:00:00"),
+            [utc("2026-06-02T05:00:00Z")]
+        );
+        assert!(occurrences("0 7 * * *", "2026-06-01T07:00:00", "2026-06-01T07:00:00").is_empty());
+    }
+
+    #[test]
+    fn test_next_occurrence_across_month_and_year_ends() {
+        // February has no 30th.
+        assert_eq!(
+            occurrences("0 0 30 * *", "2026-01-30T12:00:00", "2026-04-01T00:00:00"),
+            [utc("2026-03-29T22:00:00Z")]
+        );
+        assert_eq!(
+            occurrences("0 0 1 * *", "2026-01-31T10:00:00", "2026-02-02T00:00:00"),
+            [utc("2026-01-31T23:00:00Z")]
+        );
+        assert_eq!(
+            occurrences("@yearly", "2026-12-31T23:59:00", "2027-01-01T00:01:00"),
+            [utc("2026-12-31T23:00:00Z")]
+        );
+        assert_eq!(
+            occurrences(
+                "59 23 31 dec *",
+                "2026-06-01T00:00:00",
+                "2028-01-01T00:00:00"
+            ),
+            [utc("2026-12-31T22:59:00Z"), utc("2027-12-31T22:59:00Z")]
+        );
+    }
+
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        // 2026-02-13 is a Friday, and 2026-03-13 too.
+        let days = |expression| {
+            cron(expression)
+                .occurrences(local("2026-02-01T00:00:00"), local("2026-03-01T00:00:00"))
+                .into_iter()
+                .map(|moment| moment.day())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(days("0 12 13 * *"), [13]);
+        assert_eq!(days("0 12 * * fri"), [6, 13, 20, 27]);
+        assert_eq!(days("0 12 10 * fri"), [6, 10, 13, 20, 27]);
+        assert_eq!(days("0 12 */10 * *"), [1, 11, 21]);
+        assert_eq!(days("0 12 1-10/3 * fri"), [1, 4, 6, 7, 10, 13, 20, 27]);
+
+        // As with cron, a field starting with `*` is not restricted,
+        // even with a step: both fields must match.
+        assert_eq!(days("0 12 */5 * fri"), [6]);
+    }
+
+    #[test]
+    fn test_spring_forward_gap() {
+        // 02:30 doesn't exist, it's shifted to 03:30 (CEST).
+        assert_eq!(
+            resolve(&Zurich, "2026-03-29T02:30:00".parse().unwrap())
+                .map(|moment| moment.with_timezone(&Utc)),
+            Some(utc("2026-03-29T01:30:00Z"))
+        );
+        assert_eq!(
+            occurrences("30 2 * * *", "2026-03-28T12:00:00", "2026-03-30T12:00:00"),
+            [utc("2026-03-29T01:30:00Z"), utc("2026-03-30T00:30:00Z")]
+        );
+
+        // The shifted moment collides with the scheduled 03:30, and runs
+        // once.
+        assert_eq!(
+            occurrences("30 2,3 * * *", "2026-03-29T00:00:00", "2026-03-29T12:00:00"),
+            [utc("2026-03-29T01:30:00Z")]
+        );
+
+        // 02:00 is shifted to 03:00, which is scheduled too.
+        assert_eq!(
+            occurrences("0 * * * *", "2026-03-29T00:30:00", "2026-03-29T04:30:00"),
+            [
+                utc("2026-03-29T00:00:00Z"),
+                utc("2026-03-29T01:00:00Z"),
+                utc("2026-03-29T02:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fall_back_overlap() {
+        // 02:30 happens twice, first in CEST, then in CET: it runs once,
+        // at the first one.
+        assert_eq!(
+            resolve(&Zurich, "2026-10-25T02:30:00".parse().unwrap())
+                .map(|moment| moment.with_timezone(&Utc)),
+            Some(utc("2026-10-25T00:30:00Z"))
+        );
+        assert_eq!(
+            occurrences("30 2 * * *", "2026-10-24T12:00:00", "2026-10-26T12:00:00"),
+            [utc("2026-10-25T00:30:00Z"), utc("2026-10-26T01:30:00Z")]
+        );
+        assert_eq!(
+            occurrences("0 * * * *", "2026-10-25T00:30:00", "2026-10-25T03:30:00"),
+            [
+                utc("2026-10-24T23:00:00Z"),
+                utc("2026-10-25T00:00:00Z"),
+                utc("2026-10-25T02:00:00Z"),
+            ]
+        );
+    }
+}
@@ -1,11 +1,13 @@
-pub use crate::database::enums::AirState as VentilationState;
-use crate::database::models::*;
-use crate::events::Event;
+use crate::{configuration::Location, events::Event, sun};
 use chrono::prelude::*;
 use diesel::{prelude::*, sql_query};
+pub use hub_database::enums::AirState as VentilationState;
+use hub_database::models::*;
+use serde::{Deserialize, Serialize};
 
 pub struct Context {
     pub database_connection: PgConnection,
+    pub location: Location,
 }
 
 // That's unsafe. Really.
@@ -15,10 +17,8 @@ pub trait UpdateState {
     fn update(&self, context: &Context, new_events: &mut Vec<Event>) -> Self;
 }
 
-const HOME_LATITUDE: f64 = 46.78657339107215;
-const HOME_LONGITUDE: f64 = 6.806581635522576;
-
-#[derive(Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
 pub enum SunPeriod {
     Day,
     Night,
@@ -38,21 +38,11 @@ impl Default for Sun {
 }
 
 impl UpdateState for Sun {
-    fn update(&self, _context: &Context, new_events: &mut Vec<Event>) -> Self {
-        let now: DateTime<Local> = Local::now();
-
-        let (sunrise, sunset) = sunrise::sunrise_sunset(
-            HOME_LATITUDE,
-            HOME_LONGITUDE,
-            now.year(),
-            now.month(),
-            now.day(),
-        );
-
-        let now_utc_timestamp = DateTime::<Utc>::from(now).timestamp();
+    fn update(&self, context: &Context, new_events: &mut Vec<Event>) -> Self {
+        let position = sun::position(&context.location, Utc::now());
 
         let next_state = Self {
-            period: if sunrise <= now_utc_timestamp && now_utc_timestamp <= sunset {
+            period: if position.elevation > sun::HORIZON {
                 SunPeriod::Day
             } else {
                 SunPeriod::Night
@@ -0,0 +1,319 @@
+use crate::configuration::Location;
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+
+/// Elevation of the sun at sunrise and sunset: its upper limb touches
+/// the horizon, refraction included.
+pub const HORIZON: f64 = -0.833;
+
+/// An event of the sun, happening once a day, except near the poles.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SunEvent {
+    AstronomicalDawn,
+    NauticalDawn,
+    CivilDawn,
+    Sunrise,
+    Sunset,
+    CivilDusk,
+    NauticalDusk,
+    AstronomicalDusk,
+}
+
+impl SunEvent {
+    /// The elevation of the sun, and whether it is rising.
+    fn elevation(self) -> (f64, bool) {
+        match self {
+            Self::AstronomicalDawn => (-18., true),
+            Self::NauticalDawn => (-12., true),
+            Self::CivilDawn => (-6., true),
+            Self::Sunrise => (HORIZON, true),
+            Self::Sunset => (HORIZON, false),
+            Self::CivilDusk => (-6., false),
+            Self::NauticalDusk => (-12., false),
+            Self::AstronomicalDusk => (-18., false),
+        }
+    }
+
+    /// When the event happens on a day in `time_zone`, if it happens.
+    pub fn time<Tz: TimeZone>(
+        self,
+        location: &Location,
+        date: NaiveDate,
+        time_zone: &Tz,
+    ) -> Option<DateTime<Utc>> {
+        let (elevation, is_rising) = self.elevation();
+        let above = |time: DateTime<Utc>| position(location, time).elevation > elevation;
+
+        let start = time_zone
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?
+            .with_timezone(&Utc);
+        let step = Duration::minutes(10);
+        let mut time = start;
+
+        // Find the step where the sun crosses the elevation, then
+        // narrow it down to the second.
+        while time < start + Duration::days(1) {
+            if above(time) != is_rising && above(time + step) == is_rising {
+                let (mut from, mut to) = (time, time + step);
+
+                while to - from > Duration::seconds(1) {
+                    let middle = from + (to - from) / 2;
+
+                    if above(middle) == is_rising {
+                        to = middle;
+                    } else {
+                        from = middle;
+                    }
+                }
+
+                return Some(to);
+            }
+
+            time += step;
+        }
+
+        None
+    }
+}
+
+/// A coordinate of the sun in the sky.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum SunCoordinate {
+    /// Degrees above the horizon, negative below.
+    Elevation,
+    /// Degrees from the north, clockwise.
+    Azimuth,
+}
+
+#[derive(Debug)]
+pub struct Position {
+    pub elevation: f64,
+    pub azimuth: f64,
+}
+
+impl Position {
+    pub fn get(&self, coordinate: SunCoordinate) -> f64 {
+        match coordinate {
+            SunCoordinate::Elevation => self.elevation,
+            SunCoordinate::Azimuth => self.azimuth,
+        }
+    }
+}
+
+/// The position of the sun at a location and a time, following the
+/// NOAA solar calculations; it is precise to a fraction of a degree,
+/// refraction excluded.
+pub fn position(location: &Location, time: DateTime<Utc>) -> Position {
+    let julian_day = time.timestamp_millis() as f64 / 86400000. + 2440587.5;
+    let century = (julian_day - 2451545.) / 36525.;
+
+    let mean_longitude =
+        (280.46646 + century * (36000.76983 + century * 0.0003032)).rem_euclid(360.);
+    let mean_anomaly = 357.52911 + century * (35999.05029 - 0.0001537 * century);
+    let eccentricity = 0.016708634 - century * (0.000042037 + 0.0000001267 * century);
+
+    let center = mean_anomaly.to_radians().sin()
+        * (1.914602 - century * (0.004817 + 0.000014 * century))
+        + (2. * mean_anomaly).to_radians().sin() * (0.019993 - 0.000101 * century)
+        + (3. * mean_anomaly).to_radians().sin() * 0.000289;
+    let omega = 125.04 - 1934.136 * century;
+    let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * omega.to_radians().sin();
+
+    let obliquity = 23.
+        + (26. + (21.448 - century * (46.815 + century * (0.00059 - century * 0.001813))) / 60.)
+            / 60.
+        + 0.00256 * omega.to_radians().cos();
+    let declination = (obliquity.to_radians().sin() * apparent_longitude.to_radians().sin()).asin();
+
+    let y = (obliquity.to_radians() / 2.).tan().powi(2);
+    let (l, m) = (mean_longitude.to_radians(), mean_anomaly.to_radians());
+    // In minutes.
+    let equation_of_time = 4.
+        * (y * (2. * l).sin() - 2. * eccentricity * m.sin()
+            + 4. * eccentricity * y * m.sin() * (2. * l).cos()
+            - 0.5 * y * y * (4. * l).sin()
+            - 1.25 * eccentricity * eccentricity * (2. * m).sin())
+        .to_degrees();
+
+    let minutes = time.num_seconds_from_midnight() as f64 / 60.;
+    let true_solar_time = (minutes + equation_of_time + 4. * location.longitude).rem_euclid(1440.);
+    let hour_angle = (true_solar_time / 4. - 180.).to_radians();
+    let latitude = location.latitude.to_radians();
+
+    let elevation = (latitude.sin() * declination.sin()
+        + latitude.cos() * declination.cos() * hour_angle.cos())
+    .asin();
+    let azimuth = hour_angle
+        .sin()
+        .atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos());
+
+    Position {
+        elevation: elevation.to_degrees(),
+        azimuth: (azimuth.to_degrees() + 180.).rem_euclid(360.),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Zurich;
+
+    const ZURICH: Location = Location {
+        latitude: 47.3769,
+        longitude: 8.5417,
+    };
+    const HELSINKI: Location = Location {
+        latitude: 60.1699,
+        longitude: 24.9384,
+    };
+    const LONGYEARBYEN: Location = Location {
+        latitude: 78.2232,
+        longitude: 15.6267,
+    };
+
+    /// Days start at midnight in Zurich.
+    fn time(event: SunEvent, location: &Location, date: &str) -> Option<DateTime<Utc>> {
+        event.time(location, date.parse().unwrap(), &Zurich)
+    }
+
+    fn assert_close(event: SunEvent, date: &str, expected: &str) {
+        let expected = expected.parse::<DateTime<Utc>>().unwrap();
+        let time = time(event, &ZURICH, date)
+            .unwrap_or_else(|| panic!("{:?} must happen on {}", event, date));
+
+        assert!(
+            (time - expected).num_seconds().abs() <= 60,
+            "{:?} on {}: expected {}, got {}",
+            event,
+            date,
+            expected,
+            time
+        );
+    }
+
+    #[test]
+    fn test_events_in_zurich() {
+        use SunEvent::*;
+
+        // Equinox, solstices; the astronomical dusk of a short summer
+        // night happens after midnight, i.e. on the next day.
+        for (date, events) in [
+            (
+                "2026-03-20",
+                [
+                    (AstronomicalDawn, "2026-03-20T03:46:00Z"),
+                    (NauticalDawn, "2026-03-20T04:23:00Z"),
+                    (CivilDawn, "2026-03-20T04:59:00Z"),
+                    (Sunrise, "2026-03-20T05:29:00Z"),
+                    (Sunset, "2026-03-20T17:38:00Z"),
+                    (CivilDusk, "2026-03-20T18:09:00Z"),
+                    (NauticalDusk, "2026-03-20T18:45:00Z"),
+                    (AstronomicalDusk, "2026-03-20T19:22:00Z"),
+                ],
+            ),
+            (
+                "2026-06-21",
+                [
+                    (AstronomicalDawn, "2026-06-21T00:25:00Z"),
+                    (NauticalDawn, "2026-06-21T01:53:00Z"),
+                    (CivilDawn, "2026-06-21T02:49:00Z"),
+                    (Sunrise, "2026-06-21T03:29:00Z"),
+                    (Sunset, "2026-06-21T19:26:00Z"),
+                    (CivilDusk, "2026-06-21T20:07:00Z"),
+                    (NauticalDusk, "2026-06-21T21:02:00Z"),
+                    (AstronomicalDusk, "2026-06-20T22:30:00Z"),
+                ],
+            ),
+            (
+                "2026-12-21",
+                [
+                    (AstronomicalDawn, "2026-12-21T05:18:00Z"),
+                    (NauticalDawn, "2026-12-21T05:55:00Z"),
+                    (CivilDawn, "2026-12-21T06:34:00Z"),
+                    (Sunrise, "2026-12-21T07:10:00Z"),
+                    (Sunset, "2026-12-21T15:38:00Z"),
+                    (CivilDusk, "2026-12-21T16:13:00Z"),
+                    (NauticalDusk, "2026-12-21T16:53:00Z"),
+                    (AstronomicalDusk, "2026-12-21T17:30:00Z"),
+                ],
+            ),
+        ] {
+            for (event, expected) in events {
+                assert_close(event, date, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_events_that_do_not_happen() {
+        use SunEvent::*;
+
+        // Polar day: the sun never sets.
+        for event in [Sunrise, Sunset, CivilDawn, CivilDusk] {
+            assert_eq!(time(event, &LONGYEARBYEN, "2026-06-21"), None);
+        }
+
+        // Polar night: the sun never rises, but it's close enough to the
+        // horizon for a nautical twilight.
+        for event in [Sunrise, Sunset, CivilDawn, CivilDusk] {
+            assert_eq!(time(event, &LONGYEARBYEN, "2026-12-21"), None);
+        }
+
+        assert!(time(NauticalDawn, &LONGYEARBYEN, "2026-12-21").is_some());
+        assert!(time(NauticalDusk, &LONGYEARBYEN, "2026-12-21").is_some());
+
+        // White nights: the sun is never more than 12° below the
+        // horizon.
+        assert!(time(Sunset, &HELSINKI, "2026-06-21").is_some());
+        assert!(time(CivilDusk, &HELSINKI, "2026-06-21").is_some());
+
+        for event in [
+            NauticalDawn,
+            NauticalDusk,
+            AstronomicalDawn,
+            AstronomicalDusk,
+        ] {
+            assert_eq!(time(event, &HELSINKI, "2026-06-21"), None);
+        }
+    }
+
+    #[test]
+    fn test_position() {
+        let position = |time: &str| position(&ZURICH, time.parse().unwrap());
+
+        // At solar noon on the solstices, the elevation is
+        // 90° − latitude ± obliquity of the ecliptic.
+        for (time, elevation) in [
+            ("2026-06-21T11:27:40Z", 66.06),
+            ("2026-12-21T11:23:50Z", 19.18),
+        ] {
+            let position = position(time);
+
+            assert!(
+                (position.get(SunCoordinate::Elevation) - elevation).abs() < 0.1,
+                "{:?} at {}",
+                position,
+                time
+            );
+            assert!(
+                (position.get(SunCoordinate::Azimuth) - 180.).abs() < 0.5,
+                "{:?} at {}",
+                position,
+                time
+            );
+        }
+
+        // The sun rises in the east, and sets in the west, at the
+        // equinox.
+        let sunrise = position("2026-03-20T05:29:05Z");
+        let sunset = position("2026-03-20T17:38:20Z");
+
+        assert!((sunrise.elevation - HORIZON).abs() < 0.05, "{:?}", sunrise);
+        assert!((sunset.elevation - HORIZON).abs() < 0.05, "{:?}", sunset);
+        assert!((sunrise.azimuth - 90.).abs() < 2., "{:?}", sunrise);
+        assert!((sunset.azimuth - 270.).abs() < 2., "{:?}", sunset);
+    }
+}
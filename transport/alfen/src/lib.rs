@@ -0,0 +1,5 @@
+pub mod modbus;
+pub mod reader;
+pub mod state;
+pub mod unit;
+pub mod writer;
@@ -1,13 +1,9 @@
 mod command;
 mod configuration;
-mod modbus;
-mod reader;
-mod state;
 mod thing;
-mod unit;
-mod writer;
 
 use crate::command::*;
+use alfen::{reader, state, writer};
 use human_panic::setup_panic;
 use serde_json::to_string as to_json;
 use structopt::StructOpt;
@@ -174,6 +174,25 @@ fn make_charging_station() -> Arc<RwLock<Box<dyn Thing + 'static>>> {
         ),
     )));
 
+    thing.add_property(Box::new(BaseProperty::new(
+        "socket_delivered_energy".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "Socket delivered energy",
+                "type": "number",
+                "description": "Total energy delivered by the socket",
+                "unit": "watt-hour",
+                "readOnly": true,
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
     Arc::new(RwLock::new(Box::new(thing)))
 }
 
@@ -258,6 +277,11 @@ pub fn run(address: SocketAddr, port: Option<u16>) {
                 "socket_current",
                 socket_state.session.actual_applied_max_current
             );
+            update_property!(
+                charging_station,
+                "socket_delivered_energy",
+                socket_state.total_delivered_energy.0.round()
+            );
         }
 
         thread::sleep(time::Duration::from_secs(10));
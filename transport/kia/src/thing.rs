@@ -41,6 +41,64 @@ fn make_vehicle() -> Arc<RwLock<Box<dyn Thing + 'static>>> {
         ),
     )));
 
+    thing.add_property(Box::new(BaseProperty::new(
+        "is_charging".to_owned(),
+        json!(false),
+        None,
+        Some(
+            json!({
+                "@type": "BooleanProperty",
+                "title": "Charging",
+                "type": "boolean",
+                "description": "Whether the vehicle is charging",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
+    thing.add_property(Box::new(BaseProperty::new(
+        "remaining_range".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "Remaining range",
+                "type": "integer",
+                "description": "The distance the vehicle can drive with its state of charge",
+                "minimum": 0,
+                "unit": "kilometer",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
+    thing.add_property(Box::new(BaseProperty::new(
+        "odometer".to_owned(),
+        json!(0),
+        None,
+        Some(
+            json!({
+                "@type": "LevelProperty",
+                "title": "Odometer",
+                "type": "number",
+                "description": "The distance driven by the vehicle",
+                "minimum": 0,
+                "unit": "kilometer",
+                "readOnly": true
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ),
+    )));
+
     thing.add_property(Box::new(BaseProperty::new(
         "description".to_owned(),
         json!({}),
@@ -128,6 +186,17 @@ pub fn run(auth: Authentification, port: Option<u16>) {
                                 "state_of_charge",
                                 state.status.battery.state_of_charge,
                             );
+                            update_property!(
+                                vehicle,
+                                "is_charging",
+                                state.status.battery.is_charging,
+                            );
+                            update_property!(
+                                vehicle,
+                                "remaining_range",
+                                state.status.battery.remaining_range,
+                            );
+                            update_property!(vehicle, "odometer", &state.odometer);
                             update_property!(vehicle, "description", vehicle0);
                             update_property!(vehicle, "state", state);
                         }